@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX links_user_id_normalized_url_hash_idx;
+ALTER TABLE links DROP COLUMN normalized_url_hash;
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- Add up migration script here
+ALTER TABLE links ADD COLUMN normalized_url_hash TEXT;
+
+CREATE INDEX links_user_id_normalized_url_hash_idx ON links (user_id, normalized_url_hash);
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE links DROP COLUMN options;
\ No newline at end of file
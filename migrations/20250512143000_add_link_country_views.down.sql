@@ -0,0 +1,2 @@
+-- Add down migration script here
+DROP TABLE link_country_views;
\ No newline at end of file
@@ -0,0 +1,2 @@
+-- Add down migration script here
+DROP TABLE link_variant_views;
\ No newline at end of file
@@ -0,0 +1,7 @@
+-- Add up migration script here
+CREATE TABLE link_variant_views (
+    link_id TEXT NOT NULL REFERENCES links(id) ON DELETE CASCADE,
+    variant TEXT NOT NULL,
+    views BIGINT NOT NULL,
+    PRIMARY KEY (link_id, variant)
+);
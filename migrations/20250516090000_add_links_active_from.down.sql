@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE links DROP COLUMN active_from;
\ No newline at end of file
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE links DROP COLUMN consumed_at;
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- Add up migration script here
+ALTER TABLE links ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE INDEX links_deleted_at_idx ON links (deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE links DROP COLUMN metadata;
\ No newline at end of file
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE links ADD COLUMN metadata JSONB;
@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP TABLE link_health_checks;
+DROP INDEX links_next_health_check_at_idx;
+ALTER TABLE links DROP COLUMN next_health_check_at;
+ALTER TABLE links DROP COLUMN health_failures;
+ALTER TABLE links DROP COLUMN broken_since;
\ No newline at end of file
@@ -0,0 +1,19 @@
+-- Add up migration script here
+ALTER TABLE links ADD COLUMN broken_since TIMESTAMPTZ;
+ALTER TABLE links ADD COLUMN health_failures INT NOT NULL DEFAULT 0;
+ALTER TABLE links ADD COLUMN next_health_check_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+CREATE INDEX links_next_health_check_at_idx ON links (next_health_check_at) WHERE deleted_at IS NULL;
+
+CREATE TABLE link_health_checks (
+    id BIGSERIAL PRIMARY KEY,
+    link_id TEXT NOT NULL REFERENCES links(id) ON DELETE CASCADE,
+    checked_at TIMESTAMPTZ NOT NULL,
+    outcome TEXT NOT NULL,
+    status_code INT,
+    latency_ms BIGINT NOT NULL,
+    redirect_chain TEXT[] NOT NULL,
+    error TEXT
+);
+
+CREATE INDEX link_health_checks_link_id_idx ON link_health_checks (link_id, checked_at DESC);
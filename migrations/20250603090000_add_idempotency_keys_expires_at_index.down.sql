@@ -0,0 +1,2 @@
+-- Add down migration script here
+DROP INDEX idempotency_keys_expires_at_idx;
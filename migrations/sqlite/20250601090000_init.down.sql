@@ -0,0 +1,7 @@
+-- Add down migration script here
+DROP TABLE link_health_checks;
+DROP TABLE link_variant_views;
+DROP TABLE link_country_views;
+DROP TABLE idempotency_keys;
+DROP TABLE links;
+DROP TABLE users;
@@ -0,0 +1,71 @@
+-- Add up migration script here
+-- Timestamps are RFC 3339 text in UTC, as sqlx writes them, so they compare as strings.
+CREATE TABLE users (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    email TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    password TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE links (
+    id TEXT PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    redirect_url TEXT NOT NULL,
+    label TEXT NOT NULL,
+    views INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    last_view TEXT,
+    normalized_url_hash TEXT,
+    options TEXT NOT NULL DEFAULT '{}',
+    active_from TEXT,
+    consumed_at TEXT,
+    deleted_at TEXT,
+    metadata TEXT,
+    broken_since TEXT,
+    health_failures INTEGER NOT NULL DEFAULT 0,
+    next_health_check_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now'))
+);
+
+CREATE INDEX links_user_id_normalized_url_hash_idx ON links (user_id, normalized_url_hash);
+CREATE INDEX links_deleted_at_idx ON links (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX links_next_health_check_at_idx ON links (next_health_check_at) WHERE deleted_at IS NULL;
+
+CREATE TABLE idempotency_keys (
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    fingerprint TEXT NOT NULL,
+    link_id TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    PRIMARY KEY (user_id, key)
+);
+
+CREATE TABLE link_country_views (
+    link_id TEXT NOT NULL REFERENCES links(id) ON DELETE CASCADE,
+    country TEXT NOT NULL,
+    views INTEGER NOT NULL,
+    PRIMARY KEY (link_id, country)
+);
+
+CREATE TABLE link_variant_views (
+    link_id TEXT NOT NULL REFERENCES links(id) ON DELETE CASCADE,
+    variant TEXT NOT NULL,
+    views INTEGER NOT NULL,
+    PRIMARY KEY (link_id, variant)
+);
+
+CREATE TABLE link_health_checks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    link_id TEXT NOT NULL REFERENCES links(id) ON DELETE CASCADE,
+    checked_at TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    status_code INTEGER,
+    latency_ms INTEGER NOT NULL,
+    -- JSON array
+    redirect_chain TEXT NOT NULL,
+    error TEXT
+);
+
+CREATE INDEX link_health_checks_link_id_idx ON link_health_checks (link_id, checked_at DESC);
@@ -0,0 +1,2 @@
+-- Add up migration script here
+CREATE INDEX idempotency_keys_expires_at_idx ON idempotency_keys (expires_at);
@@ -1,13 +1,88 @@
+use std::collections::HashMap;
+
 use config::{Config, Environment, File};
 use serde::Deserialize;
 
+use crate::domain::link_manager::entity::{redirect_type::RedirectType, utm::UtmParams};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabaseBackend {
+    Postgres,
+    /// A single file, for single-node installs without a database server. Writes are
+    /// serialized and every redirect counts its view in a short write transaction, so busy
+    /// installs should use Postgres.
+    Sqlite,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct DatabaseConfig {
+    /// `postgres://...`, or `sqlite://short-link.db` (created if missing).
     pub url: String,
+    /// Postgres read replicas for redirect lookups and stats, used in turn.
+    #[serde(default)]
+    pub replica_urls: Vec<String>,
+    /// How long a modified link is read from the primary, should exceed the replication lag.
+    #[serde(default = "default_database_replica_lag_guard_ms")]
+    pub replica_lag_guard_ms: u64,
 }
+
+fn default_database_replica_lag_guard_ms() -> u64 {
+    5000
+}
+
+impl DatabaseConfig {
+    pub fn backend(&self) -> Result<DatabaseBackend, String> {
+        let scheme = self
+            .url
+            .split_once(':')
+            .map_or(self.url.as_str(), |(scheme, _)| scheme);
+        match scheme {
+            "postgres" | "postgresql" => Ok(DatabaseBackend::Postgres),
+            "sqlite" => Ok(DatabaseBackend::Sqlite),
+            // not the url itself, it may hold a password
+            _ => Err(format!("unsupported database url scheme: {scheme}")),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct RedisConfig {
+    /// A single server, Redis Cluster is not supported.
     pub url: String,
+    #[serde(default = "default_redis_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+    /// Cache lookups slower than this fall back to the database.
+    #[serde(default = "default_redis_response_timeout_ms")]
+    pub response_timeout_ms: u64,
+    /// Failed commands in a row before the cache is bypassed.
+    #[serde(default = "default_redis_failure_threshold")]
+    pub failure_threshold: u32,
+    /// How long the cache is bypassed before a trial command is sent.
+    #[serde(default = "default_redis_open_sec")]
+    pub open_sec: u64,
+    /// Retry interval while the server was never reached.
+    #[serde(default = "default_redis_reconnect_interval_sec")]
+    pub reconnect_interval_sec: u64,
+}
+
+fn default_redis_connect_timeout_ms() -> u64 {
+    1000
+}
+
+fn default_redis_response_timeout_ms() -> u64 {
+    500
+}
+
+fn default_redis_failure_threshold() -> u32 {
+    5
+}
+
+fn default_redis_open_sec() -> u64 {
+    10
+}
+
+fn default_redis_reconnect_interval_sec() -> u64 {
+    5
 }
 
 #[derive(Debug, Deserialize)]
@@ -16,11 +91,369 @@ pub struct ServerConfig {
     pub port: u16,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct LinksConfig {
+    /// Query params ignored when comparing destinations, `*` suffix matches by prefix.
+    #[serde(default = "default_tracking_params")]
+    pub tracking_params: Vec<String>,
+    /// UTM params added to every redirect unless set on the link or its campaign template.
+    #[serde(default)]
+    pub utm_defaults: UtmParams,
+    #[serde(default)]
+    pub utm_templates: HashMap<String, UtmParams>,
+    /// Used for links without their own redirect type.
+    #[serde(default)]
+    pub default_redirect_type: RedirectType,
+    /// User-Agent substrings, matched case-insensitively, of link preview crawlers that get
+    /// the social card page instead of a redirect.
+    #[serde(default = "default_crawler_user_agents")]
+    pub crawler_user_agents: Vec<String>,
+}
+
+impl Default for LinksConfig {
+    fn default() -> Self {
+        Self {
+            tracking_params: default_tracking_params(),
+            utm_defaults: UtmParams::default(),
+            utm_templates: HashMap::new(),
+            default_redirect_type: RedirectType::default(),
+            crawler_user_agents: default_crawler_user_agents(),
+        }
+    }
+}
+
+fn default_tracking_params() -> Vec<String> {
+    [
+        "utm_*", "fbclid", "gclid", "msclkid", "yclid", "mc_cid", "mc_eid",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+fn default_crawler_user_agents() -> Vec<String> {
+    [
+        "facebookexternalhit",
+        "facebot",
+        "twitterbot",
+        "slackbot",
+        "linkedinbot",
+        "discordbot",
+        "telegrambot",
+        "whatsapp",
+        "skypeuripreview",
+        "pinterest",
+        "redditbot",
+        "applebot",
+        "embedly",
+        "vkshare",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CacheConfig {
+    /// Links kept in process in front of Redis, 0 disables the local tier.
+    #[serde(default = "default_cache_local_capacity")]
+    pub local_capacity: u64,
+    /// Local entries are dropped after this even without an invalidation message.
+    #[serde(default = "default_cache_local_ttl_ms")]
+    pub local_ttl_ms: u64,
+    /// Namespace of the link cache keys in Redis.
+    #[serde(default = "default_cache_key_prefix")]
+    pub key_prefix: String,
+    /// Redis pub/sub channel used to evict links from the local tier of every instance.
+    #[serde(default = "default_cache_invalidation_channel")]
+    pub invalidation_channel: String,
+    /// Unknown link ids are remembered for this long, 0 disables negative caching.
+    #[serde(default = "default_cache_negative_ttl_sec")]
+    pub negative_ttl_sec: u64,
+    /// Keep a Bloom filter of all link ids to reject unknown ones without a lookup.
+    #[serde(default)]
+    pub bloom_filter: bool,
+    #[serde(default = "default_cache_bloom_false_positive_rate")]
+    pub bloom_false_positive_rate: f64,
+    /// The filter is rebuilt from the database to forget purged ids.
+    #[serde(default = "default_cache_bloom_rebuild_interval_sec")]
+    pub bloom_rebuild_interval_sec: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            local_capacity: default_cache_local_capacity(),
+            local_ttl_ms: default_cache_local_ttl_ms(),
+            key_prefix: default_cache_key_prefix(),
+            invalidation_channel: default_cache_invalidation_channel(),
+            negative_ttl_sec: default_cache_negative_ttl_sec(),
+            bloom_filter: false,
+            bloom_false_positive_rate: default_cache_bloom_false_positive_rate(),
+            bloom_rebuild_interval_sec: default_cache_bloom_rebuild_interval_sec(),
+        }
+    }
+}
+
+fn default_cache_local_capacity() -> u64 {
+    10000
+}
+
+fn default_cache_local_ttl_ms() -> u64 {
+    5000
+}
+
+fn default_cache_key_prefix() -> String {
+    "short-link".to_string()
+}
+
+fn default_cache_invalidation_channel() -> String {
+    "link-invalidations".to_string()
+}
+
+fn default_cache_negative_ttl_sec() -> u64 {
+    60
+}
+
+fn default_cache_bloom_false_positive_rate() -> f64 {
+    0.001
+}
+
+fn default_cache_bloom_rebuild_interval_sec() -> u64 {
+    3600
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeoIpConfig {
+    /// MaxMind format `.mmdb` file, picked up again when it changes on disk.
+    pub database_path: String,
+    #[serde(default = "default_geoip_reload_interval_sec")]
+    pub reload_interval_sec: u64,
+}
+
+fn default_geoip_reload_interval_sec() -> u64 {
+    60
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ClientIpConfig {
+    /// Peers allowed to set `X-Forwarded-For`, addresses or CIDR ranges.
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrashConfig {
+    /// Deleted links stay restorable for this long before they are purged.
+    #[serde(default = "default_trash_retention_days")]
+    pub retention_days: i64,
+    #[serde(default = "default_trash_purge_interval_sec")]
+    pub purge_interval_sec: u64,
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: default_trash_retention_days(),
+            purge_interval_sec: default_trash_purge_interval_sec(),
+        }
+    }
+}
+
+fn default_trash_retention_days() -> i64 {
+    30
+}
+
+fn default_trash_purge_interval_sec() -> u64 {
+    3600
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetadataConfig {
+    /// Fetch title, description, favicon and preview image of new link destinations.
+    #[serde(default = "default_metadata_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_metadata_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Larger pages are cut off, metadata is expected in the `<head>`.
+    #[serde(default = "default_metadata_max_body_bytes")]
+    pub max_body_bytes: usize,
+    #[serde(default = "default_metadata_max_redirects")]
+    pub max_redirects: usize,
+    #[serde(default = "default_metadata_concurrency")]
+    pub concurrency: usize,
+    /// Lets the fetcher reach loopback and private addresses, only meant for local testing.
+    #[serde(default)]
+    pub allow_private_networks: bool,
+}
+
+impl Default for MetadataConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_metadata_enabled(),
+            timeout_ms: default_metadata_timeout_ms(),
+            max_body_bytes: default_metadata_max_body_bytes(),
+            max_redirects: default_metadata_max_redirects(),
+            concurrency: default_metadata_concurrency(),
+            allow_private_networks: false,
+        }
+    }
+}
+
+fn default_metadata_enabled() -> bool {
+    true
+}
+
+fn default_metadata_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_metadata_max_body_bytes() -> usize {
+    512 * 1024
+}
+
+fn default_metadata_max_redirects() -> usize {
+    5
+}
+
+fn default_metadata_concurrency() -> usize {
+    4
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HealthCheckConfig {
+    /// Periodically request link destinations to find broken ones.
+    #[serde(default = "default_health_check_enabled")]
+    pub enabled: bool,
+    /// How often due links are looked up.
+    #[serde(default = "default_health_check_poll_interval_sec")]
+    pub poll_interval_sec: u64,
+    /// Time between two checks of a healthy link.
+    #[serde(default = "default_health_check_interval_sec")]
+    pub check_interval_sec: i64,
+    /// Consecutive failed checks before a link counts as broken.
+    #[serde(default = "default_health_check_failure_threshold")]
+    pub failure_threshold: i32,
+    /// First retry delay after a failed check, doubled on every further failure.
+    #[serde(default = "default_health_check_retry_backoff_sec")]
+    pub retry_backoff_sec: i64,
+    #[serde(default = "default_health_check_max_backoff_sec")]
+    pub max_backoff_sec: i64,
+    #[serde(default = "default_health_check_batch_size")]
+    pub batch_size: i64,
+    #[serde(default = "default_health_check_concurrency")]
+    pub concurrency: usize,
+    #[serde(default = "default_health_check_per_host_concurrency")]
+    pub per_host_concurrency: usize,
+    #[serde(default = "default_health_check_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_health_check_max_redirects")]
+    pub max_redirects: usize,
+    /// Check history is kept for this long.
+    #[serde(default = "default_health_check_history_days")]
+    pub history_days: i64,
+    /// How often checks older than `history_days` are deleted.
+    #[serde(default = "default_health_check_prune_interval_sec")]
+    pub prune_interval_sec: u64,
+    /// Lets the checker reach loopback and private addresses, only meant for local testing.
+    #[serde(default)]
+    pub allow_private_networks: bool,
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_health_check_enabled(),
+            poll_interval_sec: default_health_check_poll_interval_sec(),
+            check_interval_sec: default_health_check_interval_sec(),
+            failure_threshold: default_health_check_failure_threshold(),
+            retry_backoff_sec: default_health_check_retry_backoff_sec(),
+            max_backoff_sec: default_health_check_max_backoff_sec(),
+            batch_size: default_health_check_batch_size(),
+            concurrency: default_health_check_concurrency(),
+            per_host_concurrency: default_health_check_per_host_concurrency(),
+            timeout_ms: default_health_check_timeout_ms(),
+            max_redirects: default_health_check_max_redirects(),
+            history_days: default_health_check_history_days(),
+            prune_interval_sec: default_health_check_prune_interval_sec(),
+            allow_private_networks: false,
+        }
+    }
+}
+
+fn default_health_check_enabled() -> bool {
+    true
+}
+
+fn default_health_check_poll_interval_sec() -> u64 {
+    60
+}
+
+fn default_health_check_interval_sec() -> i64 {
+    6 * 3600
+}
+
+fn default_health_check_failure_threshold() -> i32 {
+    3
+}
+
+fn default_health_check_retry_backoff_sec() -> i64 {
+    300
+}
+
+fn default_health_check_max_backoff_sec() -> i64 {
+    6 * 3600
+}
+
+fn default_health_check_batch_size() -> i64 {
+    100
+}
+
+fn default_health_check_concurrency() -> usize {
+    16
+}
+
+fn default_health_check_per_host_concurrency() -> usize {
+    2
+}
+
+fn default_health_check_timeout_ms() -> u64 {
+    10000
+}
+
+fn default_health_check_max_redirects() -> usize {
+    10
+}
+
+fn default_health_check_history_days() -> i64 {
+    30
+}
+
+fn default_health_check_prune_interval_sec() -> u64 {
+    3600
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ConfigSettings {
     pub database: DatabaseConfig,
-    pub redis: RedisConfig,
+    /// Without it links are only cached in process, fine for a single instance.
+    #[serde(default)]
+    pub redis: Option<RedisConfig>,
     pub server: ServerConfig,
+    #[serde(default)]
+    pub links: LinksConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub geoip: Option<GeoIpConfig>,
+    #[serde(default)]
+    pub client_ip: ClientIpConfig,
+    #[serde(default)]
+    pub trash: TrashConfig,
+    #[serde(default)]
+    pub metadata: MetadataConfig,
+    #[serde(default)]
+    pub health_check: HealthCheckConfig,
 }
 
 pub fn load_config() -> Result<ConfigSettings, config::ConfigError> {
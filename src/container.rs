@@ -1,80 +1,498 @@
 use eyre::Context;
-use redis::aio::ConnectionManager;
+use futures_util::StreamExt;
+use redis::RedisError;
 use solar::trx_factory::SqlxTrxFactory;
-use sqlx::{Pool, Postgres};
-use std::sync::Arc;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool};
+use std::{str::FromStr, sync::Arc, time::Duration};
+use tokio::sync::{Semaphore, mpsc};
 
 use crate::{
-    config::{ConfigSettings, load_config},
+    config::{
+        ConfigSettings, DatabaseBackend, DatabaseConfig, HealthCheckConfig, MetadataConfig,
+        TrashConfig, load_config,
+    },
     domain::{
-        auth::{infra::persistence::AuthPersistenceRepo, service::AuthService},
+        auth::{
+            self,
+            infra::{persistence::AuthPersistenceRepo, sqlite::AuthSqliteRepo},
+            service::AuthService,
+        },
         link_manager::{
-            infra::persistence::LinkManagerPersistenceRepo, service::LinkManagerService,
+            self,
+            entity::link::LinkId,
+            infra::{
+                health_checker::HealthChecker,
+                link_cache::{NoLinkCache, RedisLinkCache},
+                metadata_fetcher::MetadataFetcher,
+                persistence::LinkManagerPersistenceRepo,
+                read_replicas::ReadReplicas,
+                sqlite::LinkManagerSqliteRepo,
+            },
+            service::{LinkCache, LinkManagerService, MetadataJob},
         },
         user_manager::{
-            infra::persistence::UserManagerPersistenceRepo, service::UserManagerService,
+            self,
+            infra::{persistence::UserManagerPersistenceRepo, sqlite::UserManagerSqliteRepo},
+            service::UserManagerService,
         },
     },
+    tools::{
+        any_trx::AnyTrxFactory,
+        client_ip::{ClientIpResolver, IpRange},
+        failure_log::FailureLog,
+        geoip::{GeoIpResolver, spawn_reloader},
+        redis_connection::{RedisConnection, spawn_reconnector},
+        sqlite_trx::SqliteTrxFactory,
+    },
 };
 
 const LINK_CACHE_EXPIRATION_SEC: u64 = 3600;
+const IDEMPOTENCY_KEY_EXPIRATION_SEC: u64 = 86400;
+const IDEMPOTENCY_KEY_PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+const METADATA_QUEUE_SIZE: usize = 1000;
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+/// How long a write waits for the SQLite write lock.
+const SQLITE_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub type AppAuthService = AuthService<Arc<dyn auth::service::PersistenceRepo>, AnyTrxFactory>;
+pub type AppLinkManagerService =
+    LinkManagerService<Arc<dyn link_manager::service::PersistenceRepo>, AnyTrxFactory>;
+pub type AppUserManagerService =
+    UserManagerService<Arc<dyn user_manager::service::PersistenceRepo>, AnyTrxFactory>;
 
 pub struct Container {
     pub config: ConfigSettings,
-    pub pool: Pool<Postgres>,
-    pub trx_factory: SqlxTrxFactory,
-    pub auth_service: Arc<AuthService<AuthPersistenceRepo, SqlxTrxFactory>>,
-    pub link_manager_service: Arc<LinkManagerService<LinkManagerPersistenceRepo, SqlxTrxFactory>>,
-    pub user_manager_service: Arc<UserManagerService<UserManagerPersistenceRepo, SqlxTrxFactory>>,
+    pub trx_factory: AnyTrxFactory,
+    pub auth_service: Arc<AppAuthService>,
+    pub link_manager_service: Arc<AppLinkManagerService>,
+    pub user_manager_service: Arc<AppUserManagerService>,
+    pub client_ip_resolver: Arc<ClientIpResolver>,
+    pub geo_ip_resolver: Arc<GeoIpResolver>,
+    /// `None` when Redis is not configured.
+    pub redis_connection: Option<Arc<RedisConnection>>,
     pub server_address: String,
 }
 
+fn spawn_trash_purger(
+    link_manager_service: Arc<AppLinkManagerService>,
+    trash_config: &TrashConfig,
+) {
+    let retention = chrono::Duration::days(trash_config.retention_days);
+    let interval = Duration::from_secs(trash_config.purge_interval_sec);
+
+    tokio::spawn(async move {
+        let failures = FailureLog::new("purge deleted links");
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match link_manager_service.purge_trash(retention).await {
+                Ok(purged) => {
+                    failures.succeeded();
+                    if purged > 0 {
+                        println!("purged {purged} deleted links");
+                    }
+                }
+                Err(e) => failures.failed(e),
+            }
+        }
+    });
+}
+
+fn spawn_idempotency_key_purger(link_manager_service: Arc<AppLinkManagerService>) {
+    tokio::spawn(async move {
+        let failures = FailureLog::new("purge expired idempotency keys");
+        let mut ticker = tokio::time::interval(IDEMPOTENCY_KEY_PURGE_INTERVAL);
+        loop {
+            ticker.tick().await;
+            match link_manager_service
+                .purge_expired_idempotency_records()
+                .await
+            {
+                Ok(purged) => {
+                    failures.succeeded();
+                    if purged > 0 {
+                        println!("purged {purged} expired idempotency keys");
+                    }
+                }
+                Err(e) => failures.failed(e),
+            }
+        }
+    });
+}
+
+/// Applies invalidations published by any instance to the local cache tier and the Bloom
+/// filter.
+fn spawn_cache_invalidation_listener(
+    link_manager_service: Arc<AppLinkManagerService>,
+    redis_client: redis::Client,
+    channel: String,
+) {
+    tokio::spawn(async move {
+        let failures = FailureLog::new("subscribe to cache invalidations");
+        loop {
+            if let Err(e) =
+                listen_for_invalidations(&link_manager_service, &redis_client, &channel, &failures)
+                    .await
+            {
+                failures.failed(e);
+            }
+            // messages sent while disconnected are lost
+            link_manager_service.on_invalidations_lost();
+            tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+        }
+    });
+}
+
+async fn listen_for_invalidations(
+    link_manager_service: &AppLinkManagerService,
+    redis_client: &redis::Client,
+    channel: &str,
+    failures: &FailureLog,
+) -> Result<(), RedisError> {
+    let mut pubsub = redis_client.get_async_pubsub().await?;
+    pubsub.subscribe(channel).await?;
+    failures.succeeded();
+
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        let link_id: String = message.get_payload()?;
+        link_manager_service.on_invalidation_message(&LinkId::from_string(link_id));
+    }
+
+    Ok(())
+}
+
+/// Replays the invalidations missed while Redis was bypassed, once it is back.
+fn spawn_invalidation_replayer(link_cache: Arc<RedisLinkCache>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            link_cache.replay_pending_invalidations().await;
+        }
+    });
+}
+
+fn spawn_link_id_filter_rebuilder(
+    link_manager_service: Arc<AppLinkManagerService>,
+    interval: Duration,
+) {
+    tokio::spawn(async move {
+        let failures = FailureLog::new("rebuild link id filter");
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match link_manager_service.rebuild_link_id_filter().await {
+                Ok(_) => failures.succeeded(),
+                Err(e) => failures.failed(e),
+            }
+        }
+    });
+}
+
+fn spawn_metadata_worker(
+    link_manager_service: Arc<AppLinkManagerService>,
+    mut metadata_jobs: mpsc::Receiver<MetadataJob>,
+    metadata_config: &MetadataConfig,
+) {
+    let fetcher =
+        Arc::new(MetadataFetcher::new(metadata_config).expect("failed to build metadata fetcher"));
+    let permits = Arc::new(Semaphore::new(metadata_config.concurrency.max(1)));
+
+    tokio::spawn(async move {
+        while let Some(job) = metadata_jobs.recv().await {
+            let Ok(permit) = permits.clone().acquire_owned().await else {
+                break;
+            };
+            let fetcher = fetcher.clone();
+            let link_manager_service = link_manager_service.clone();
+
+            tokio::spawn(async move {
+                let _permit = permit;
+                let metadata = match fetcher.fetch(&job.url).await {
+                    Ok(metadata) => metadata,
+                    Err(e) => {
+                        println!("failed to fetch metadata for {}: {e}", job.link_id);
+                        return;
+                    }
+                };
+                if let Err(e) = link_manager_service
+                    .save_link_metadata(&job.link_id, metadata)
+                    .await
+                {
+                    println!("failed to save metadata for {}: {e:?}", job.link_id);
+                }
+            });
+        }
+    });
+}
+
+fn spawn_health_check_pruner(
+    link_manager_service: Arc<AppLinkManagerService>,
+    health_check_config: &HealthCheckConfig,
+) {
+    let history = chrono::Duration::days(health_check_config.history_days);
+    let interval = Duration::from_secs(health_check_config.prune_interval_sec);
+
+    tokio::spawn(async move {
+        let failures = FailureLog::new("prune health checks");
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match link_manager_service.prune_health_checks(history).await {
+                Ok(_) => failures.succeeded(),
+                Err(e) => failures.failed(e),
+            }
+        }
+    });
+}
+
+fn spawn_health_checker(
+    link_manager_service: Arc<AppLinkManagerService>,
+    health_check_config: &HealthCheckConfig,
+) {
+    let checker =
+        Arc::new(HealthChecker::new(health_check_config).expect("failed to build health checker"));
+    let permits = Arc::new(Semaphore::new(health_check_config.concurrency.max(1)));
+    let config = Arc::new(health_check_config.clone());
+
+    let record_failures = Arc::new(FailureLog::new("record health checks"));
+
+    tokio::spawn(async move {
+        let claim_failures = FailureLog::new("claim links for health check");
+        let mut ticker = tokio::time::interval(Duration::from_secs(config.poll_interval_sec));
+        loop {
+            ticker.tick().await;
+            let targets = match link_manager_service
+                .claim_health_check_targets(config.batch_size)
+                .await
+            {
+                Ok(targets) => {
+                    claim_failures.succeeded();
+                    targets
+                }
+                Err(e) => {
+                    claim_failures.failed(e);
+                    continue;
+                }
+            };
+
+            for target in targets {
+                let Ok(permit) = permits.clone().acquire_owned().await else {
+                    return;
+                };
+                let checker = checker.clone();
+                let link_manager_service = link_manager_service.clone();
+                let config = config.clone();
+                let record_failures = record_failures.clone();
+
+                tokio::spawn(async move {
+                    let _permit = permit;
+                    let check = checker.check(&target.url).await;
+                    match link_manager_service
+                        .record_health_check(&target.link_id, check, &config)
+                        .await
+                    {
+                        Ok(_) => record_failures.succeeded(),
+                        Err(e) => record_failures.failed(e),
+                    }
+                });
+            }
+        }
+    });
+}
+
+/// Repos of the backend picked by the database url scheme.
+struct Persistence {
+    trx_factory: AnyTrxFactory,
+    auth_repo: Arc<dyn auth::service::PersistenceRepo>,
+    link_manager_repo: Arc<dyn link_manager::service::PersistenceRepo>,
+    user_manager_repo: Arc<dyn user_manager::service::PersistenceRepo>,
+}
+
+async fn connect_database(database_config: &DatabaseConfig) -> Persistence {
+    match database_config.backend().unwrap() {
+        DatabaseBackend::Postgres => {
+            let pool = sqlx::PgPool::connect(&database_config.url)
+                .await
+                .expect("failed to connect to db");
+
+            let trx_factory = SqlxTrxFactory::new(pool);
+            sqlx::migrate!("./migrations")
+                .run(trx_factory.pool())
+                .await
+                .context("failed to run migrations")
+                .unwrap();
+
+            let mut link_manager_repo = LinkManagerPersistenceRepo::new(trx_factory.clone());
+            if !database_config.replica_urls.is_empty() {
+                let replica_pools = database_config
+                    .replica_urls
+                    .iter()
+                    // a replica down at startup is skipped until it is back
+                    .map(|url| sqlx::PgPool::connect_lazy(url).expect("invalid replica url"))
+                    .collect();
+                link_manager_repo = link_manager_repo.with_read_replicas(ReadReplicas::new(
+                    replica_pools,
+                    Duration::from_millis(database_config.replica_lag_guard_ms),
+                ));
+            }
+
+            Persistence {
+                auth_repo: Arc::new(AuthPersistenceRepo::new(trx_factory.clone())),
+                link_manager_repo: Arc::new(link_manager_repo),
+                user_manager_repo: Arc::new(UserManagerPersistenceRepo::new(trx_factory.clone())),
+                trx_factory: AnyTrxFactory::Postgres(trx_factory),
+            }
+        }
+        DatabaseBackend::Sqlite => {
+            assert!(
+                database_config.replica_urls.is_empty(),
+                "read replicas require postgres"
+            );
+
+            let options = SqliteConnectOptions::from_str(&database_config.url)
+                .expect("invalid sqlite url")
+                .create_if_missing(true)
+                .journal_mode(SqliteJournalMode::Wal)
+                .busy_timeout(SQLITE_BUSY_TIMEOUT);
+            let pool = SqlitePool::connect_with(options)
+                .await
+                .expect("failed to open sqlite db");
+
+            let trx_factory = SqliteTrxFactory::new(pool);
+            sqlx::migrate!("./migrations/sqlite")
+                .run(trx_factory.pool())
+                .await
+                .context("failed to run migrations")
+                .unwrap();
+
+            Persistence {
+                auth_repo: Arc::new(AuthSqliteRepo::new(trx_factory.clone())),
+                link_manager_repo: Arc::new(LinkManagerSqliteRepo::new(trx_factory.clone())),
+                user_manager_repo: Arc::new(UserManagerSqliteRepo::new(trx_factory.clone())),
+                trx_factory: AnyTrxFactory::Sqlite(trx_factory),
+            }
+        }
+    }
+}
+
 pub async fn build_container() -> Arc<Container> {
-    let config = load_config().unwrap();
+    build_container_with(load_config().unwrap()).await
+}
 
-    let redis_client =
-        redis::Client::open(config.redis.url.to_string()).expect("failed to connect to redis");
-    let redis_connection_manager = ConnectionManager::new(redis_client)
-        .await
-        .expect("failed to create connection manager");
+pub async fn build_container_with(config: ConfigSettings) -> Arc<Container> {
+    // Redis is optional and may be down at startup, links are then served from the database
+    let mut redis_client = None;
+    let mut redis_connection = None;
+    let link_cache: Arc<dyn LinkCache> = match &config.redis {
+        Some(redis_config) => {
+            let client =
+                redis::Client::open(redis_config.url.to_string()).expect("invalid redis url");
+            let connection = Arc::new(RedisConnection::connect(client.clone(), redis_config).await);
+            let reconnect_interval = Duration::from_secs(redis_config.reconnect_interval_sec);
+            spawn_reconnector(connection.clone(), reconnect_interval);
 
-    let server_address = format!("{}:{}", config.server.host, config.server.port);
+            let link_cache = Arc::new(RedisLinkCache::new(connection.clone(), &config.cache));
+            spawn_invalidation_replayer(link_cache.clone(), reconnect_interval);
+
+            redis_client = Some(client);
+            redis_connection = Some(connection);
+            link_cache
+        }
+        None => Arc::new(NoLinkCache),
+    };
 
-    let pool = sqlx::PgPool::connect(&config.database.url.to_string())
-        .await
-        .expect("failed to connect to db");
+    let server_address = format!("{}:{}", config.server.host, config.server.port);
 
-    let trx_factory = SqlxTrxFactory::new(pool.clone());
-    sqlx::migrate!("./migrations")
-        .run(trx_factory.pool())
-        .await
-        .context("failed to run migrations")
-        .unwrap();
+    let persistence = connect_database(&config.database).await;
+    let trx_factory = persistence.trx_factory;
 
-    let auth_persistence_repo = AuthPersistenceRepo::new(trx_factory.clone());
-    let auth_service = Arc::new(AuthService::new(auth_persistence_repo, trx_factory.clone()));
+    let auth_service = Arc::new(AuthService::new(persistence.auth_repo, trx_factory.clone()));
 
-    let link_manager_persistence_repo = LinkManagerPersistenceRepo::new(trx_factory.clone());
-    let link_manager_service = Arc::new(LinkManagerService::new(
-        link_manager_persistence_repo,
+    let mut link_manager_service = LinkManagerService::new(
+        persistence.link_manager_repo,
         trx_factory.clone(),
-        redis_connection_manager.clone(),
+        link_cache,
         LINK_CACHE_EXPIRATION_SEC,
-    ));
+        IDEMPOTENCY_KEY_EXPIRATION_SEC,
+        config.links.clone(),
+    )
+    .with_cache_config(&config.cache);
+    let mut metadata_jobs = None;
+    if config.metadata.enabled {
+        let (sender, receiver) = mpsc::channel(METADATA_QUEUE_SIZE);
+        link_manager_service = link_manager_service.with_metadata_jobs(sender);
+        metadata_jobs = Some(receiver);
+    }
+    let link_manager_service = Arc::new(link_manager_service);
+    if let Some(metadata_jobs) = metadata_jobs {
+        spawn_metadata_worker(
+            link_manager_service.clone(),
+            metadata_jobs,
+            &config.metadata,
+        );
+    }
+
+    // without Redis this is the only instance, its own changes are applied locally
+    if let Some(redis_client) = redis_client
+        && link_manager_service.listens_for_invalidations()
+    {
+        spawn_cache_invalidation_listener(
+            link_manager_service.clone(),
+            redis_client,
+            config.cache.invalidation_channel.clone(),
+        );
+    }
+
+    if link_manager_service.has_link_id_filter() {
+        spawn_link_id_filter_rebuilder(
+            link_manager_service.clone(),
+            Duration::from_secs(config.cache.bloom_rebuild_interval_sec),
+        );
+    }
+
+    spawn_trash_purger(link_manager_service.clone(), &config.trash);
+    spawn_idempotency_key_purger(link_manager_service.clone());
+    if config.health_check.enabled {
+        spawn_health_checker(link_manager_service.clone(), &config.health_check);
+        spawn_health_check_pruner(link_manager_service.clone(), &config.health_check);
+    }
 
-    let user_manager_persistence_repo = UserManagerPersistenceRepo::new(trx_factory.clone());
     let user_manager_service = Arc::new(UserManagerService::new(
-        user_manager_persistence_repo,
+        persistence.user_manager_repo,
         trx_factory.clone(),
     ));
 
+    let trusted_proxies = config
+        .client_ip
+        .trusted_proxies
+        .iter()
+        .map(|proxy| IpRange::parse(proxy).expect("invalid trusted proxy"))
+        .collect();
+    let client_ip_resolver = Arc::new(ClientIpResolver::new(trusted_proxies));
+
+    let geo_ip_resolver = match &config.geoip {
+        Some(geoip_config) => {
+            let resolver = Arc::new(GeoIpResolver::open(&geoip_config.database_path));
+            spawn_reloader(
+                resolver.clone(),
+                Duration::from_secs(geoip_config.reload_interval_sec),
+            );
+            resolver
+        }
+        None => Arc::new(GeoIpResolver::disabled()),
+    };
+
     Arc::new(Container {
         config,
-        pool,
         trx_factory,
         auth_service,
         link_manager_service,
         user_manager_service,
+        client_ip_resolver,
+        geo_ip_resolver,
+        redis_connection,
         server_address,
     })
 }
@@ -0,0 +1,73 @@
+use std::sync::{Arc, Mutex};
+
+use solar::trx_factory::TrxContext;
+
+use super::super::entity::user::User;
+use super::super::service::{PersistenceError, PersistenceRepo};
+
+/// The `users` table, shared with the user manager repo.
+#[derive(Debug, Default)]
+pub struct InMemoryUsers {
+    users: Mutex<Vec<User>>,
+}
+
+impl InMemoryUsers {
+    /// Inserts or, for a known email, updates name and `updated_at` like the Postgres repos.
+    pub fn save(&self, user: User) -> i32 {
+        let mut users = self.users.lock().expect("users lock poisoned");
+        if let Some(existing) = users.iter_mut().find(|u| u.email == user.email) {
+            existing.name = user.name;
+            existing.updated_at = chrono::Utc::now();
+            return existing.id;
+        }
+
+        let id = users.len() as i32 + 1;
+        users.push(User { id, ..user });
+        id
+    }
+
+    pub fn find(&self, predicate: impl Fn(&User) -> bool) -> Option<User> {
+        self.users
+            .lock()
+            .expect("users lock poisoned")
+            .iter()
+            .find(|u| predicate(u))
+            .cloned()
+    }
+}
+
+pub struct InMemoryAuthRepo {
+    users: Arc<InMemoryUsers>,
+}
+
+impl InMemoryAuthRepo {
+    pub fn new(users: Arc<InMemoryUsers>) -> Self {
+        Self { users }
+    }
+}
+
+#[async_trait::async_trait]
+impl PersistenceRepo for InMemoryAuthRepo {
+    async fn save_user(&self, user: User, _ctx: TrxContext) -> Result<i32, PersistenceError> {
+        Ok(self.users.save(user))
+    }
+
+    async fn login(
+        &self,
+        email: String,
+        password: String,
+        _ctx: TrxContext,
+    ) -> Result<Option<User>, PersistenceError> {
+        Ok(self
+            .users
+            .find(|u| u.email == email && u.password == password))
+    }
+
+    async fn get_user_by_email(
+        &self,
+        email: &str,
+        _ctx: TrxContext,
+    ) -> Result<Option<User>, PersistenceError> {
+        Ok(self.users.find(|u| u.email == email))
+    }
+}
@@ -1 +1,4 @@
+#[cfg(test)]
+pub mod in_memory;
 pub mod persistence;
+pub mod sqlite;
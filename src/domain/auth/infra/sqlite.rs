@@ -0,0 +1,103 @@
+use chrono::Utc;
+use eyre::Context;
+use solar::trx_factory::TrxContext;
+
+use crate::tools::sqlite_trx::SqliteTrxFactory;
+
+use super::super::entity::user::User;
+use super::super::service::{PersistenceError, PersistenceRepo};
+
+pub struct AuthSqliteRepo {
+    trx_factory: SqliteTrxFactory,
+}
+
+impl AuthSqliteRepo {
+    pub fn new(trx_factory: SqliteTrxFactory) -> Self {
+        Self { trx_factory }
+    }
+}
+
+#[async_trait::async_trait]
+impl PersistenceRepo for AuthSqliteRepo {
+    async fn save_user(&self, user: User, ctx: TrxContext) -> Result<i32, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let (user_id,) = sqlx::query_as::<_, (i32,)>(
+            r#"
+            INSERT INTO users (name, email, password, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $4)
+            ON CONFLICT (email) DO UPDATE
+            SET name = excluded.name,
+                updated_at = excluded.updated_at
+            RETURNING id
+            "#,
+        )
+        .bind(user.name)
+        .bind(user.email)
+        .bind(user.password)
+        .bind(Utc::now())
+        .fetch_one(&mut **trx)
+        .await
+        .context("failed to save user")?;
+
+        Ok(user_id)
+    }
+
+    async fn login(
+        &self,
+        email: String,
+        password: String,
+        ctx: TrxContext,
+    ) -> Result<Option<User>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            SELECT * FROM users
+            WHERE email = $1
+            AND password = $2
+            "#,
+        )
+        .bind(email)
+        .bind(password)
+        .fetch_optional(&mut **trx)
+        .await
+        .context("failed to find user with email and password")?;
+
+        Ok(user)
+    }
+
+    async fn get_user_by_email(
+        &self,
+        email: &str,
+        ctx: TrxContext,
+    ) -> Result<Option<User>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE email = $1")
+            .bind(email)
+            .fetch_optional(&mut **trx)
+            .await
+            .context("failed to find user with email")?;
+
+        Ok(user)
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use solar::trx_factory::{TrxContext, TrxFactory, TrxFactoryError};
 
 use super::entity::user::User;
@@ -28,6 +30,31 @@ pub trait PersistenceRepo: Send + Sync {
     ) -> Result<Option<User>, PersistenceError>;
 }
 
+/// Lets the repo be chosen at runtime, see `DatabaseConfig`.
+#[async_trait::async_trait]
+impl<T: PersistenceRepo + ?Sized> PersistenceRepo for Arc<T> {
+    async fn save_user(&self, user: User, ctx: TrxContext) -> Result<i32, PersistenceError> {
+        (**self).save_user(user, ctx).await
+    }
+
+    async fn login(
+        &self,
+        email: String,
+        password: String,
+        ctx: TrxContext,
+    ) -> Result<Option<User>, PersistenceError> {
+        (**self).login(email, password, ctx).await
+    }
+
+    async fn get_user_by_email(
+        &self,
+        email: &str,
+        ctx: TrxContext,
+    ) -> Result<Option<User>, PersistenceError> {
+        (**self).get_user_by_email(email, ctx).await
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum AuthError {
     #[error("trx factory error: {0}")]
@@ -97,3 +124,83 @@ where
         Ok(user)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        domain::auth::infra::in_memory::{InMemoryAuthRepo, InMemoryUsers},
+        tools::in_memory_trx::InMemoryTrxFactory,
+    };
+
+    fn auth_service() -> AuthService<InMemoryAuthRepo, InMemoryTrxFactory> {
+        let users = Arc::new(InMemoryUsers::default());
+        AuthService::new(InMemoryAuthRepo::new(users), InMemoryTrxFactory)
+    }
+
+    async fn register(service: &AuthService<InMemoryAuthRepo, InMemoryTrxFactory>) -> i32 {
+        service
+            .register(
+                "alice".to_string(),
+                "alice@example.com".to_string(),
+                "secret".to_string(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn register_rejects_taken_email() {
+        let service = auth_service();
+        register(&service).await;
+
+        let result = service
+            .register(
+                "bob".to_string(),
+                "alice@example.com".to_string(),
+                "other".to_string(),
+            )
+            .await;
+
+        assert!(matches!(result, Err(AuthError::UserAlreadyExists)));
+    }
+
+    #[tokio::test]
+    async fn login_returns_registered_user() {
+        let service = auth_service();
+        let user_id = register(&service).await;
+
+        let user = service
+            .login("alice@example.com".to_string(), "secret".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(user.id, user_id);
+        assert_eq!(user.name, "alice");
+    }
+
+    #[tokio::test]
+    async fn login_rejects_wrong_password() {
+        let service = auth_service();
+        register(&service).await;
+
+        let result = service
+            .login("alice@example.com".to_string(), "wrong".to_string())
+            .await;
+
+        assert!(matches!(result, Err(AuthError::IncorrectEmailOrPassword)));
+    }
+
+    #[tokio::test]
+    async fn login_rejects_unknown_email() {
+        let service = auth_service();
+
+        let result = service
+            .login("nobody@example.com".to_string(), "secret".to_string())
+            .await;
+
+        assert!(matches!(result, Err(AuthError::IncorrectEmailOrPassword)));
+    }
+}
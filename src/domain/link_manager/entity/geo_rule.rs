@@ -0,0 +1,35 @@
+use utoipa::ToSchema;
+
+use crate::tools::geoip::GeoLocation;
+
+/// Destination for visitors from a country, optionally narrowed down to a region.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct GeoRule {
+    /// ISO 3166-1 alpha-2 country code, e.g. `DE`
+    pub country: String,
+    /// ISO 3166-2 subdivision code without the country prefix, e.g. `BY`
+    pub region: Option<String>,
+    pub destination: String,
+}
+
+impl GeoRule {
+    pub fn matches(&self, location: &GeoLocation) -> bool {
+        if !self.country.eq_ignore_ascii_case(&location.country) {
+            return false;
+        }
+
+        match (&self.region, &location.region) {
+            (None, _) => true,
+            (Some(rule_region), Some(region)) => rule_region.eq_ignore_ascii_case(region),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// First matching rule wins, so region rules should go before country-wide ones.
+pub fn geo_destination<'a>(rules: &'a [GeoRule], location: &GeoLocation) -> Option<&'a str> {
+    rules
+        .iter()
+        .find(|rule| rule.matches(location))
+        .map(|rule| rule.destination.as_str())
+}
@@ -0,0 +1,57 @@
+use super::link::LinkId;
+
+#[derive(Debug, Clone)]
+pub struct IdempotencyKey {
+    pub key: String,
+    pub fingerprint: String,
+}
+
+impl IdempotencyKey {
+    pub fn new(key: String, fingerprint: String) -> Self {
+        Self { key, fingerprint }
+    }
+}
+
+#[readonly::make]
+#[derive(Debug, Clone)]
+pub struct IdempotencyRecord {
+    pub user_id: i32,
+    pub key: String,
+    pub fingerprint: String,
+    pub link_id: LinkId,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl IdempotencyRecord {
+    pub fn new(user_id: i32, key: IdempotencyKey, link_id: LinkId, expr_sec: u64) -> Self {
+        let created_at = chrono::Utc::now();
+
+        Self {
+            user_id,
+            key: key.key,
+            fingerprint: key.fingerprint,
+            link_id,
+            created_at,
+            expires_at: created_at + chrono::Duration::seconds(expr_sec as i64),
+        }
+    }
+
+    pub fn from_parts(
+        user_id: i32,
+        key: String,
+        fingerprint: String,
+        link_id: LinkId,
+        created_at: chrono::DateTime<chrono::Utc>,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        Self {
+            user_id,
+            key,
+            fingerprint,
+            link_id,
+            created_at,
+            expires_at,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::BTreeMap;
+
+use utoipa::ToSchema;
+
+use crate::tools::accept_language::negotiate_language;
+
+/// Destinations per language tag, negotiated against the visitor's `Accept-Language`.
+/// `default` is used when none fits the visitor and no time window is open.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(default)]
+pub struct LanguageRules {
+    /// Language tag, e.g. `de` or `pt-BR`, to destination
+    pub locales: BTreeMap<String, String>,
+    pub default: Option<String>,
+}
+
+impl LanguageRules {
+    pub fn is_empty(&self) -> bool {
+        self.locales.is_empty() && self.default.is_none()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let invalid_tag = self.locales.keys().find(|tag| {
+            tag.is_empty()
+                || !tag.split('-').all(|subtag| {
+                    !subtag.is_empty() && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+                })
+        });
+        match invalid_tag {
+            Some(tag) => Err(format!("invalid language tag: {tag}")),
+            None => Ok(()),
+        }
+    }
+
+    pub fn destinations(&self) -> impl Iterator<Item = &str> {
+        self.locales
+            .values()
+            .chain(self.default.as_ref())
+            .map(String::as_str)
+    }
+
+    /// The locale fitting the visitor, `default` is left to the caller.
+    pub fn destination_for(&self, accept_language: Option<&str>) -> Option<&str> {
+        if self.locales.is_empty() {
+            return None;
+        }
+
+        accept_language
+            .and_then(|header| negotiate_language(header, self.locales.keys().map(String::as_str)))
+            .and_then(|tag| self.locales.get(tag))
+            .map(String::as_str)
+    }
+}
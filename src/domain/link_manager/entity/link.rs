@@ -1,9 +1,14 @@
 use nanoid::nanoid;
+
+use super::{link_metadata::LinkMetadata, link_options::LinkOptions};
 use std::fmt::Display;
 use utoipa::ToSchema;
 
+/// Ids drawn before giving up on finding a free one.
+pub const MAX_LINK_ID_ATTEMPTS: usize = 10;
+
 #[readonly::make]
-#[derive(Debug, Eq, PartialEq, Clone, serde::Serialize, serde::Deserialize, ToSchema)]
+#[derive(Debug, Eq, PartialEq, Hash, Clone, serde::Serialize, serde::Deserialize, ToSchema)]
 pub struct LinkId {
     pub value: String,
 }
@@ -35,10 +40,36 @@ pub struct Link {
     pub views: i64,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub last_view: Option<chrono::DateTime<chrono::Utc>>,
+    pub normalized_url_hash: Option<String>,
+    /// The link redirects only from this moment on.
+    #[serde(default)]
+    pub active_from: Option<chrono::DateTime<chrono::Utc>>,
+    /// Set once a one-time link has been used.
+    #[serde(default)]
+    pub consumed_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Set while the link is in the trash.
+    #[serde(default)]
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Filled in by the metadata fetcher after the link is created.
+    #[serde(default)]
+    pub metadata: Option<LinkMetadata>,
+    /// Set by the health checker while the destination is down.
+    #[serde(default)]
+    pub broken_since: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub options: LinkOptions,
 }
 
 impl Link {
-    pub fn new(id: LinkId, user_id: i32, redirect_url: String, label: String) -> Self {
+    pub fn new(
+        id: LinkId,
+        user_id: i32,
+        redirect_url: String,
+        label: String,
+        normalized_url_hash: Option<String>,
+        active_from: Option<chrono::DateTime<chrono::Utc>>,
+        options: LinkOptions,
+    ) -> Self {
         Self {
             id,
             user_id,
@@ -47,9 +78,17 @@ impl Link {
             views: 0,
             created_at: chrono::Utc::now(),
             last_view: None,
+            normalized_url_hash,
+            active_from,
+            consumed_at: None,
+            deleted_at: None,
+            metadata: None,
+            broken_since: None,
+            options,
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn from_parts(
         id: LinkId,
         user_id: i32,
@@ -58,6 +97,13 @@ impl Link {
         views: i64,
         created_at: chrono::DateTime<chrono::Utc>,
         last_view: Option<chrono::DateTime<chrono::Utc>>,
+        normalized_url_hash: Option<String>,
+        active_from: Option<chrono::DateTime<chrono::Utc>>,
+        consumed_at: Option<chrono::DateTime<chrono::Utc>>,
+        deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+        metadata: Option<LinkMetadata>,
+        broken_since: Option<chrono::DateTime<chrono::Utc>>,
+        options: LinkOptions,
     ) -> Self {
         Self {
             id,
@@ -67,6 +113,25 @@ impl Link {
             views,
             created_at,
             last_view,
+            normalized_url_hash,
+            active_from,
+            consumed_at,
+            deleted_at,
+            metadata,
+            broken_since,
+            options,
+        }
+    }
+
+    pub fn is_active_at(&self, at: chrono::DateTime<chrono::Utc>) -> bool {
+        self.active_from.is_none_or(|active_from| active_from <= at)
+    }
+
+    /// `redirect_url`, or the fallback url while the destination is broken.
+    pub fn primary_destination(&self) -> &str {
+        match (&self.broken_since, &self.options.fallback_url) {
+            (Some(_), Some(fallback_url)) => fallback_url,
+            _ => &self.redirect_url,
         }
     }
 }
@@ -0,0 +1,110 @@
+use std::str::FromStr;
+
+use utoipa::ToSchema;
+
+use super::link::LinkId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthOutcome {
+    Healthy,
+    Broken,
+    /// The host asked us to slow down, the check says nothing about the destination.
+    Throttled,
+}
+
+impl HealthOutcome {
+    /// Final status of the redirect chain. Login walls still count as a live page.
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            429 => HealthOutcome::Throttled,
+            401 | 403 => HealthOutcome::Healthy,
+            status if status < 400 => HealthOutcome::Healthy,
+            _ => HealthOutcome::Broken,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HealthOutcome::Healthy => "healthy",
+            HealthOutcome::Broken => "broken",
+            HealthOutcome::Throttled => "throttled",
+        }
+    }
+}
+
+impl FromStr for HealthOutcome {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "healthy" => Ok(HealthOutcome::Healthy),
+            "broken" => Ok(HealthOutcome::Broken),
+            "throttled" => Ok(HealthOutcome::Throttled),
+            _ => Err(format!("unknown health outcome: {s}")),
+        }
+    }
+}
+
+/// One request to a link destination, following its redirects.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct HealthCheck {
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+    pub outcome: HealthOutcome,
+    /// Status of the last response, missing when the request failed.
+    pub status_code: Option<u16>,
+    pub latency_ms: i64,
+    /// Every url visited after the destination itself.
+    pub redirect_chain: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// Link due for a health check.
+#[derive(Debug, Clone)]
+pub struct HealthCheckTarget {
+    pub link_id: LinkId,
+    pub url: String,
+}
+
+/// Consecutive failed checks of a link, and since when it counts as broken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkHealthState {
+    pub failures: i32,
+    pub broken_since: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl LinkHealthState {
+    /// State after `check`. A link becomes broken on its `failure_threshold`-th failed check
+    /// in a row and is healthy again after the first successful one. Throttled checks change
+    /// nothing.
+    pub fn after(&self, check: &HealthCheck, failure_threshold: i32) -> Self {
+        match check.outcome {
+            HealthOutcome::Healthy => Self {
+                failures: 0,
+                broken_since: None,
+            },
+            HealthOutcome::Broken => {
+                let failures = self.failures.saturating_add(1);
+                let broken_since = match self.broken_since {
+                    None if failures >= failure_threshold => Some(check.checked_at),
+                    broken_since => broken_since,
+                };
+
+                Self {
+                    failures,
+                    broken_since,
+                }
+            }
+            HealthOutcome::Throttled => self.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LinkHealth {
+    pub link_id: LinkId,
+    pub redirect_url: String,
+    pub label: String,
+    pub state: LinkHealthState,
+    pub last_check: Option<HealthCheck>,
+}
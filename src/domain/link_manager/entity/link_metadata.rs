@@ -0,0 +1,25 @@
+use utoipa::ToSchema;
+
+use crate::tools::html_meta::PageMetadata;
+
+/// What the metadata fetcher found on the destination page.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct LinkMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub favicon: Option<String>,
+    pub image: Option<String>,
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<PageMetadata> for LinkMetadata {
+    fn from(page: PageMetadata) -> Self {
+        Self {
+            title: page.title,
+            description: page.description,
+            favicon: page.favicon,
+            image: page.image,
+            fetched_at: chrono::Utc::now(),
+        }
+    }
+}
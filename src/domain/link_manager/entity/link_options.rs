@@ -0,0 +1,40 @@
+use utoipa::ToSchema;
+
+use super::{
+    geo_rule::GeoRule, language_rules::LanguageRules, open_graph::OpenGraph,
+    redirect_type::RedirectType, routing_rules::RoutingRules, schedule::Schedule, utm::UtmParams,
+    variant::Variant,
+};
+
+/// Per-link redirect behaviour, stored as a single json column.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(default)]
+pub struct LinkOptions {
+    pub utm: UtmParams,
+    /// Name of a campaign template from the `links.utm_templates` config.
+    pub utm_template: Option<String>,
+    /// Forward the incoming query string to the destination.
+    pub forward_query: bool,
+    /// Forward path segments after the link id to the destination.
+    pub forward_path: bool,
+    /// Burn after the first view, later hits get 410 Gone.
+    pub one_time: bool,
+    /// Falls back to `links.default_redirect_type` from config.
+    pub redirect_type: Option<RedirectType>,
+    /// User-Agent based destinations, evaluated before `redirect_url`.
+    pub routing: RoutingRules,
+    /// Country and region based destinations, evaluated after `routing`.
+    pub geo: Vec<GeoRule>,
+    /// `Accept-Language` based destinations, evaluated after `geo`.
+    pub languages: LanguageRules,
+    /// Time-window destinations, evaluated after the `languages` locales but before their
+    /// default.
+    pub schedule: Schedule,
+    /// Social card served to link preview crawlers.
+    pub og: OpenGraph,
+    /// Weighted A/B destinations, used in place of `redirect_url` when no routing, geo,
+    /// language or schedule rule matches.
+    pub variants: Vec<Variant>,
+    /// Used in place of `redirect_url` while the health checker reports it broken.
+    pub fallback_url: Option<String>,
+}
@@ -0,0 +1,15 @@
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct CountryViews {
+    pub country: String,
+    pub views: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct VariantViews {
+    pub variant: String,
+    pub destination: String,
+    pub weight: u32,
+    pub views: i64,
+}
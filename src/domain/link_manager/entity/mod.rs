@@ -1 +1,16 @@
-pub mod link;
\ No newline at end of file
+pub mod geo_rule;
+pub mod idempotency_key;
+pub mod language_rules;
+pub mod link;
+pub mod link_health;
+pub mod link_metadata;
+pub mod link_options;
+pub mod link_stats;
+pub mod new_link;
+pub mod open_graph;
+pub mod redirect_context;
+pub mod redirect_type;
+pub mod routing_rules;
+pub mod schedule;
+pub mod utm;
+pub mod variant;
@@ -0,0 +1,60 @@
+use utoipa::ToSchema;
+
+use super::link_options::LinkOptions;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NewLink {
+    pub redirect_url: String,
+    pub label: String,
+    pub active_from: Option<chrono::DateTime<chrono::Utc>>,
+    pub options: LinkOptions,
+}
+
+impl NewLink {
+    pub fn new(redirect_url: String, label: String) -> Self {
+        Self {
+            redirect_url,
+            label,
+            active_from: None,
+            options: LinkOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: LinkOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_active_from(mut self, active_from: Option<chrono::DateTime<chrono::Utc>>) -> Self {
+        self.active_from = active_from;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        validate_destination(&self.redirect_url)
+    }
+}
+
+pub fn validate_destination(destination: &str) -> Result<(), String> {
+    if destination.trim().is_empty() {
+        return Err("redirect url is empty".to_string());
+    }
+
+    match url::Url::parse(destination) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Ok(()),
+        Ok(url) => Err(format!("unsupported url scheme: {}", url.scheme())),
+        Err(e) => Err(format!("invalid redirect url: {e}")),
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct LinkRowError {
+    pub row: usize,
+    pub message: String,
+}
+
+impl LinkRowError {
+    pub fn new(row: usize, message: String) -> Self {
+        Self { row, message }
+    }
+}
@@ -0,0 +1,26 @@
+use utoipa::ToSchema;
+
+use super::new_link::validate_destination;
+
+/// Social card shown by chat apps and social networks instead of the destination's own.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(default)]
+pub struct OpenGraph {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Absolute http(s) image url
+    pub image: Option<String>,
+}
+
+impl OpenGraph {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.description.is_none() && self.image.is_none()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.image {
+            Some(image) => validate_destination(image).map_err(|e| format!("og image: {e}")),
+            None => Ok(()),
+        }
+    }
+}
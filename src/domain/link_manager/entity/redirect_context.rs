@@ -0,0 +1,106 @@
+use std::{collections::HashSet, net::IpAddr};
+
+use chrono::{DateTime, Utc};
+use url::{Url, form_urlencoded};
+
+use crate::tools::geoip::GeoLocation;
+
+/// What the visitor sent along with a short link hit.
+#[derive(Debug, Clone)]
+pub struct RedirectContext {
+    /// Raw query string, without the leading `?`.
+    pub query: Option<String>,
+    /// Path after the link id, e.g. `docs/page` for `/view/{id}/docs/page`.
+    pub path: Option<String>,
+    pub user_agent: Option<String>,
+    pub accept_language: Option<String>,
+    pub client_ip: Option<IpAddr>,
+    pub geo: Option<GeoLocation>,
+    /// Variant name from the visitor's sticky cookie.
+    pub variant: Option<String>,
+    /// When the hit happened, so every rule of one request sees the same time.
+    pub at: DateTime<Utc>,
+}
+
+impl RedirectContext {
+    pub fn new(query: Option<String>, path: Option<String>) -> Self {
+        Self {
+            query,
+            path,
+            user_agent: None,
+            accept_language: None,
+            client_ip: None,
+            geo: None,
+            variant: None,
+            at: Utc::now(),
+        }
+    }
+
+    pub fn with_user_agent(mut self, user_agent: Option<String>) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
+    pub fn with_accept_language(mut self, accept_language: Option<String>) -> Self {
+        self.accept_language = accept_language;
+        self
+    }
+
+    pub fn with_client(mut self, client_ip: IpAddr, geo: Option<GeoLocation>) -> Self {
+        self.client_ip = Some(client_ip);
+        self.geo = geo;
+        self
+    }
+
+    pub fn with_variant(mut self, variant: Option<String>) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Appends the forwarded path segments after the destination path.
+    pub fn apply_path(&self, destination: &str) -> String {
+        let Some(path) = self
+            .path
+            .as_deref()
+            .filter(|p| !p.trim_matches('/').is_empty())
+        else {
+            return destination.to_string();
+        };
+        let Ok(mut url) = Url::parse(destination) else {
+            return destination.to_string();
+        };
+
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments
+                .pop_if_empty()
+                .extend(path.split('/').filter(|s| !s.is_empty()));
+        } else {
+            return destination.to_string();
+        }
+
+        url.to_string()
+    }
+
+    /// Appends the incoming query params. On a key conflict the destination value wins and
+    /// the incoming one is dropped.
+    pub fn apply_query(&self, destination: &str) -> String {
+        let Some(query) = self.query.as_deref().filter(|q| !q.is_empty()) else {
+            return destination.to_string();
+        };
+        let Ok(mut url) = Url::parse(destination) else {
+            return destination.to_string();
+        };
+
+        let existing: HashSet<String> = url.query_pairs().map(|(k, _)| k.into_owned()).collect();
+        let forwarded: Vec<(String, String)> = form_urlencoded::parse(query.as_bytes())
+            .filter(|(key, _)| !existing.contains(key.as_ref()))
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        if forwarded.is_empty() {
+            return destination.to_string();
+        }
+
+        url.query_pairs_mut().extend_pairs(forwarded);
+        url.to_string()
+    }
+}
@@ -0,0 +1,50 @@
+use http::StatusCode;
+use utoipa::ToSchema;
+
+const PERMANENT_REDIRECT_MAX_AGE_SEC: u64 = 86400;
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RedirectType {
+    /// 301, cacheable, for SEO links
+    MovedPermanently,
+    /// 302
+    Found,
+    /// 303
+    #[default]
+    SeeOther,
+    /// 307, keeps the request method
+    TemporaryRedirect,
+    /// 308, cacheable, keeps the request method
+    PermanentRedirect,
+}
+
+impl RedirectType {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            RedirectType::MovedPermanently => StatusCode::MOVED_PERMANENTLY,
+            RedirectType::Found => StatusCode::FOUND,
+            RedirectType::SeeOther => StatusCode::SEE_OTHER,
+            RedirectType::TemporaryRedirect => StatusCode::TEMPORARY_REDIRECT,
+            RedirectType::PermanentRedirect => StatusCode::PERMANENT_REDIRECT,
+        }
+    }
+
+    pub fn is_permanent(&self) -> bool {
+        matches!(
+            self,
+            RedirectType::MovedPermanently | RedirectType::PermanentRedirect
+        )
+    }
+
+    /// Temporary redirects must reach us on every click, otherwise views are not counted.
+    pub fn cache_control(&self) -> String {
+        if self.is_permanent() {
+            format!("public, max-age={PERMANENT_REDIRECT_MAX_AGE_SEC}")
+        } else {
+            "no-store".to_string()
+        }
+    }
+}
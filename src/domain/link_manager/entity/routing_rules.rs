@@ -0,0 +1,65 @@
+use utoipa::ToSchema;
+
+use crate::tools::user_agent::{Browser, DeviceClass, Os, ParsedUserAgent};
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct DeviceRule {
+    pub device: DeviceClass,
+    pub destination: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct OsRule {
+    pub os: Os,
+    pub destination: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct BrowserRule {
+    pub browser: Browser,
+    pub destination: String,
+}
+
+/// User-Agent based destinations. OS rules are checked first, then device class, then
+/// browser; within each list the first match wins. No match falls back to the link
+/// `redirect_url`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(default)]
+pub struct RoutingRules {
+    pub os: Vec<OsRule>,
+    pub device: Vec<DeviceRule>,
+    pub browser: Vec<BrowserRule>,
+}
+
+impl RoutingRules {
+    pub fn is_empty(&self) -> bool {
+        self.os.is_empty() && self.device.is_empty() && self.browser.is_empty()
+    }
+
+    pub fn destinations(&self) -> impl Iterator<Item = &str> {
+        self.os
+            .iter()
+            .map(|r| r.destination.as_str())
+            .chain(self.device.iter().map(|r| r.destination.as_str()))
+            .chain(self.browser.iter().map(|r| r.destination.as_str()))
+    }
+
+    pub fn destination_for(&self, user_agent: &ParsedUserAgent) -> Option<&str> {
+        self.os
+            .iter()
+            .find(|r| r.os == user_agent.os)
+            .map(|r| r.destination.as_str())
+            .or_else(|| {
+                self.device
+                    .iter()
+                    .find(|r| r.device == user_agent.device)
+                    .map(|r| r.destination.as_str())
+            })
+            .or_else(|| {
+                self.browser
+                    .iter()
+                    .find(|r| r.browser == user_agent.browser)
+                    .map(|r| r.destination.as_str())
+            })
+    }
+}
@@ -0,0 +1,141 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use utoipa::ToSchema;
+
+/// How far ahead boundaries are searched, enough to cover every weekday once.
+const BOUNDARY_LOOKAHEAD_DAYS: i64 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(weekday: chrono::Weekday) -> Self {
+        match weekday {
+            chrono::Weekday::Mon => Weekday::Monday,
+            chrono::Weekday::Tue => Weekday::Tuesday,
+            chrono::Weekday::Wed => Weekday::Wednesday,
+            chrono::Weekday::Thu => Weekday::Thursday,
+            chrono::Weekday::Fri => Weekday::Friday,
+            chrono::Weekday::Sat => Weekday::Saturday,
+            chrono::Weekday::Sun => Weekday::Sunday,
+        }
+    }
+}
+
+/// Destination for a daily time range in the schedule timezone, `start` inclusive and
+/// `end` exclusive. A range with `end` before `start` runs past midnight and belongs to
+/// the day it starts on. No `days` means every day.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct TimeWindow {
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    #[schema(value_type = String, example = "09:00:00")]
+    pub start: NaiveTime,
+    #[schema(value_type = String, example = "17:00:00")]
+    pub end: NaiveTime,
+    pub destination: String,
+}
+
+impl TimeWindow {
+    fn on_day(&self, weekday: chrono::Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&weekday.into())
+    }
+
+    fn contains(&self, local: NaiveDateTime) -> bool {
+        let time = local.time();
+        if self.start <= self.end {
+            self.on_day(local.weekday()) && self.start <= time && time < self.end
+        } else {
+            (self.on_day(local.weekday()) && time >= self.start)
+                || (self.on_day(local.weekday().pred()) && time < self.end)
+        }
+    }
+}
+
+/// Time-window destinations, evaluated in `timezone` (an IANA name, UTC if unset).
+/// The first matching window wins.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(default)]
+pub struct Schedule {
+    #[schema(example = "Europe/Berlin")]
+    pub timezone: Option<String>,
+    pub windows: Vec<TimeWindow>,
+}
+
+impl Schedule {
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    fn tz(&self) -> Tz {
+        self.timezone
+            .as_deref()
+            .and_then(|tz| tz.parse().ok())
+            .unwrap_or(Tz::UTC)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(timezone) = &self.timezone {
+            timezone
+                .parse::<Tz>()
+                .map_err(|_| format!("unknown timezone: {timezone}"))?;
+        }
+        if self.windows.iter().any(|w| w.start == w.end) {
+            return Err("time window start and end must differ".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub fn destinations(&self) -> impl Iterator<Item = &str> {
+        self.windows.iter().map(|w| w.destination.as_str())
+    }
+
+    pub fn destination_at(&self, at: DateTime<Utc>) -> Option<&str> {
+        let local = at.with_timezone(&self.tz()).naive_local();
+        self.windows
+            .iter()
+            .find(|w| w.contains(local))
+            .map(|w| w.destination.as_str())
+    }
+
+    /// Next moment after `at` when a window opens or closes, so cached state can expire
+    /// exactly when the destination may change.
+    pub fn next_boundary(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let tz = self.tz();
+        let today = at.with_timezone(&tz).date_naive();
+
+        (-1..BOUNDARY_LOOKAHEAD_DAYS)
+            .map(|offset| today + Duration::days(offset))
+            .flat_map(|day| {
+                self.windows
+                    .iter()
+                    .filter(move |w| w.on_day(day.weekday()))
+                    .flat_map(move |w| {
+                        let end_day = if w.end < w.start {
+                            day.succ_opt()
+                        } else {
+                            Some(day)
+                        };
+                        [
+                            Some(day.and_time(w.start)),
+                            end_day.map(|d| d.and_time(w.end)),
+                        ]
+                    })
+            })
+            .flatten()
+            .filter_map(|local| tz.from_local_datetime(&local).earliest())
+            .map(|boundary| boundary.with_timezone(&Utc))
+            .filter(|boundary| *boundary > at)
+            .min()
+    }
+}
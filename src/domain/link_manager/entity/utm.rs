@@ -0,0 +1,61 @@
+use std::collections::HashSet;
+
+use url::Url;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(default)]
+pub struct UtmParams {
+    pub source: Option<String>,
+    pub medium: Option<String>,
+    pub campaign: Option<String>,
+    pub term: Option<String>,
+    pub content: Option<String>,
+}
+
+impl UtmParams {
+    /// Field by field, values of `self` win over `fallback`.
+    pub fn or(&self, fallback: &UtmParams) -> UtmParams {
+        UtmParams {
+            source: self.source.clone().or(fallback.source.clone()),
+            medium: self.medium.clone().or(fallback.medium.clone()),
+            campaign: self.campaign.clone().or(fallback.campaign.clone()),
+            term: self.term.clone().or(fallback.term.clone()),
+            content: self.content.clone().or(fallback.content.clone()),
+        }
+    }
+
+    pub fn pairs(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("utm_source", &self.source),
+            ("utm_medium", &self.medium),
+            ("utm_campaign", &self.campaign),
+            ("utm_term", &self.term),
+            ("utm_content", &self.content),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.as_deref().map(|v| (key, v)))
+        .collect()
+    }
+
+    /// Appends the params missing from `destination`. Params already present in the
+    /// destination are kept as is and never duplicated.
+    pub fn apply_to(&self, destination: &str) -> String {
+        let Ok(mut url) = Url::parse(destination) else {
+            return destination.to_string();
+        };
+
+        let existing: HashSet<String> = url.query_pairs().map(|(k, _)| k.into_owned()).collect();
+        let missing: Vec<(&str, &str)> = self
+            .pairs()
+            .into_iter()
+            .filter(|(key, _)| !existing.contains(*key))
+            .collect();
+        if missing.is_empty() {
+            return destination.to_string();
+        }
+
+        url.query_pairs_mut().extend_pairs(missing);
+        url.to_string()
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+/// One destination of an A/B split. Traffic is shared between variants in proportion to
+/// their weights.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct Variant {
+    /// Unique within the link, reported in variant stats and kept in the sticky cookie.
+    pub name: String,
+    pub destination: String,
+    pub weight: u32,
+}
+
+pub fn validate_variants(variants: &[Variant]) -> Result<(), String> {
+    let mut names = HashSet::new();
+    for variant in variants {
+        if variant.name.trim().is_empty() {
+            return Err("variant name must not be empty".to_string());
+        }
+        if !names.insert(variant.name.as_str()) {
+            return Err(format!("duplicate variant name: {}", variant.name));
+        }
+        if variant.weight == 0 {
+            return Err(format!(
+                "variant {} must have a positive weight",
+                variant.name
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Picks a variant for a visitor. A `sticky` variant name, e.g. from a cookie, is kept as
+/// long as the link still has it; otherwise the variant is derived from a hash of `seed`,
+/// so the same visitor lands on the same variant.
+pub fn pick_variant<'a>(
+    variants: &'a [Variant],
+    sticky: Option<&str>,
+    seed: &[u8],
+) -> Option<&'a Variant> {
+    if let Some(sticky) = sticky
+        && let Some(variant) = variants.iter().find(|v| v.name == sticky)
+    {
+        return Some(variant);
+    }
+
+    let total_weight: u64 = variants.iter().map(|v| v.weight as u64).sum();
+    if total_weight == 0 {
+        return None;
+    }
+
+    let hash = Sha256::digest(seed);
+    let mut bucket =
+        u64::from_be_bytes(hash[..8].try_into().expect("sha256 is 32 bytes")) % total_weight;
+    for variant in variants {
+        if bucket < variant.weight as u64 {
+            return Some(variant);
+        }
+        bucket -= variant.weight as u64;
+    }
+
+    None
+}
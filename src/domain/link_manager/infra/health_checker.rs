@@ -0,0 +1,247 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use reqwest::{Client, Url, header, redirect};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::metadata_fetcher::{MetadataFetchError, PublicOnlyResolver, check_url};
+use crate::{
+    config::HealthCheckConfig,
+    domain::link_manager::entity::link_health::{HealthCheck, HealthOutcome},
+};
+
+const USER_AGENT: &str = "short-link-health-checker/0.1";
+const MIN_HOST_BACKOFF: Duration = Duration::from_secs(5);
+const MAX_HOST_BACKOFF: Duration = Duration::from_secs(300);
+/// Idle hosts are forgotten once this many are tracked.
+const MAX_TRACKED_HOSTS: usize = 10000;
+
+#[derive(thiserror::Error, Debug)]
+pub enum HealthCheckError {
+    #[error("refused url: {0}")]
+    RefusedUrl(#[from] MetadataFetchError),
+    #[error("request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("too many redirects")]
+    TooManyRedirects,
+    #[error("redirect without location")]
+    MissingLocation,
+}
+
+struct HostState {
+    permits: Arc<Semaphore>,
+    /// No requests are sent to the host before this.
+    resume_at: Option<Instant>,
+    backoff: Duration,
+}
+
+struct Response {
+    status: u16,
+    retry_after: Option<Duration>,
+}
+
+/// Requests link destinations with at most `per_host_concurrency` checks per host. Hosts
+/// that throttle or time out are left alone for a while, doubling the pause every time.
+pub struct HealthChecker {
+    client: Client,
+    max_redirects: usize,
+    per_host_concurrency: usize,
+    allow_private_networks: bool,
+    hosts: Mutex<HashMap<String, HostState>>,
+}
+
+impl HealthChecker {
+    pub fn new(config: &HealthCheckConfig) -> Result<Self, HealthCheckError> {
+        let client = Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .connect_timeout(Duration::from_millis(config.timeout_ms))
+            .user_agent(USER_AGENT)
+            .no_proxy()
+            .dns_resolver(Arc::new(PublicOnlyResolver {
+                allow_private_networks: config.allow_private_networks,
+            }))
+            // redirects are followed by hand to record the chain
+            .redirect(redirect::Policy::none())
+            .build()?;
+
+        Ok(Self {
+            client,
+            max_redirects: config.max_redirects,
+            per_host_concurrency: config.per_host_concurrency.max(1),
+            allow_private_networks: config.allow_private_networks,
+            hosts: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub async fn check(&self, url: &str) -> HealthCheck {
+        let url = match Url::parse(url) {
+            Ok(url) => url,
+            Err(_) => {
+                let error = MetadataFetchError::InvalidUrl(url.to_string());
+                return failed_check(chrono::Utc::now(), 0, Vec::new(), error.into());
+            }
+        };
+        let host = url.host_str().unwrap_or_default().to_string();
+        let _permit = self.acquire_host(&host).await;
+
+        let checked_at = chrono::Utc::now();
+        let started = Instant::now();
+        let mut redirect_chain = Vec::new();
+        let result = self.follow(url, &mut redirect_chain).await;
+        let latency_ms = started.elapsed().as_millis() as i64;
+
+        match result {
+            Ok(response) => {
+                if matches!(response.status, 429 | 503) {
+                    self.back_off_host(&host, response.retry_after);
+                } else {
+                    self.reset_host(&host);
+                }
+
+                HealthCheck {
+                    checked_at,
+                    outcome: HealthOutcome::from_status(response.status),
+                    status_code: Some(response.status),
+                    latency_ms,
+                    redirect_chain,
+                    error: None,
+                }
+            }
+            Err(e) => {
+                if let HealthCheckError::Request(e) = &e
+                    && (e.is_timeout() || e.is_connect())
+                {
+                    self.back_off_host(&host, None);
+                }
+
+                failed_check(checked_at, latency_ms, redirect_chain, e)
+            }
+        }
+    }
+
+    async fn follow(
+        &self,
+        mut url: Url,
+        redirect_chain: &mut Vec<String>,
+    ) -> Result<Response, HealthCheckError> {
+        loop {
+            check_url(&url, self.allow_private_networks)?;
+
+            let mut response = self.client.head(url.clone()).send().await?;
+            // not every server implements HEAD
+            if matches!(response.status().as_u16(), 405 | 501) {
+                response = self.client.get(url.clone()).send().await?;
+            }
+
+            let status = response.status();
+            if !status.is_redirection() {
+                let retry_after = response
+                    .headers()
+                    .get(header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.trim().parse().ok())
+                    .map(Duration::from_secs);
+
+                return Ok(Response {
+                    status: status.as_u16(),
+                    retry_after,
+                });
+            }
+
+            if redirect_chain.len() >= self.max_redirects {
+                return Err(HealthCheckError::TooManyRedirects);
+            }
+            let location = response
+                .headers()
+                .get(header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .ok_or(HealthCheckError::MissingLocation)?;
+            url = url
+                .join(location)
+                .map_err(|_| MetadataFetchError::InvalidUrl(location.to_string()))?;
+            redirect_chain.push(url.to_string());
+        }
+    }
+
+    async fn acquire_host(&self, host: &str) -> OwnedSemaphorePermit {
+        let permits = {
+            let mut hosts = self.hosts.lock().unwrap();
+            if hosts.len() > MAX_TRACKED_HOSTS {
+                let now = Instant::now();
+                hosts.retain(|_, state| {
+                    state.permits.available_permits() < self.per_host_concurrency
+                        || state.resume_at.is_some_and(|resume_at| resume_at > now)
+                });
+            }
+
+            hosts
+                .entry(host.to_string())
+                .or_insert_with(|| HostState {
+                    permits: Arc::new(Semaphore::new(self.per_host_concurrency)),
+                    resume_at: None,
+                    backoff: MIN_HOST_BACKOFF,
+                })
+                .permits
+                .clone()
+        };
+        let permit = permits
+            .acquire_owned()
+            .await
+            .expect("host semaphore is never closed");
+
+        loop {
+            let resume_at = self
+                .hosts
+                .lock()
+                .unwrap()
+                .get(host)
+                .and_then(|state| state.resume_at);
+            match resume_at {
+                Some(resume_at) if resume_at > Instant::now() => {
+                    tokio::time::sleep_until(resume_at.into()).await
+                }
+                _ => break,
+            }
+        }
+
+        permit
+    }
+
+    fn back_off_host(&self, host: &str, retry_after: Option<Duration>) {
+        let mut hosts = self.hosts.lock().unwrap();
+        let Some(state) = hosts.get_mut(host) else {
+            return;
+        };
+
+        let pause = retry_after.unwrap_or(state.backoff).min(MAX_HOST_BACKOFF);
+        state.resume_at = Some(Instant::now() + pause);
+        state.backoff = (state.backoff * 2).min(MAX_HOST_BACKOFF);
+    }
+
+    fn reset_host(&self, host: &str) {
+        let mut hosts = self.hosts.lock().unwrap();
+        if let Some(state) = hosts.get_mut(host) {
+            state.resume_at = None;
+            state.backoff = MIN_HOST_BACKOFF;
+        }
+    }
+}
+
+fn failed_check(
+    checked_at: chrono::DateTime<chrono::Utc>,
+    latency_ms: i64,
+    redirect_chain: Vec<String>,
+    error: HealthCheckError,
+) -> HealthCheck {
+    HealthCheck {
+        checked_at,
+        outcome: HealthOutcome::Broken,
+        status_code: None,
+        latency_ms,
+        redirect_chain,
+        error: Some(error.to_string()),
+    }
+}
@@ -0,0 +1,715 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use solar::trx_factory::TrxContext;
+
+use crate::domain::link_manager::entity::idempotency_key::IdempotencyRecord;
+use crate::domain::link_manager::entity::link::{Link, LinkId, MAX_LINK_ID_ATTEMPTS};
+use crate::domain::link_manager::entity::link_health::{
+    HealthCheck, HealthCheckTarget, LinkHealth, LinkHealthState,
+};
+use crate::domain::link_manager::entity::link_metadata::LinkMetadata;
+use crate::domain::link_manager::entity::link_options::LinkOptions;
+use crate::domain::link_manager::entity::link_stats::CountryViews;
+use crate::domain::link_manager::service::{
+    CacheLookup, LinkCache, PersistenceError, PersistenceRepo,
+};
+use crate::tools::redis_connection::RedisStatus;
+
+/// A row of the `links` table.
+#[derive(Debug, Clone)]
+struct LinkRow {
+    id: LinkId,
+    user_id: i32,
+    redirect_url: String,
+    label: String,
+    views: i64,
+    created_at: chrono::DateTime<chrono::Utc>,
+    last_view: Option<chrono::DateTime<chrono::Utc>>,
+    normalized_url_hash: Option<String>,
+    active_from: Option<chrono::DateTime<chrono::Utc>>,
+    consumed_at: Option<chrono::DateTime<chrono::Utc>>,
+    deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    metadata: Option<LinkMetadata>,
+    broken_since: Option<chrono::DateTime<chrono::Utc>>,
+    options: LinkOptions,
+    health_failures: i32,
+    next_health_check_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<Link> for LinkRow {
+    fn from(link: Link) -> Self {
+        Self {
+            id: link.id.clone(),
+            user_id: link.user_id,
+            redirect_url: link.redirect_url.clone(),
+            label: link.label.clone(),
+            views: link.views,
+            created_at: link.created_at,
+            last_view: link.last_view,
+            normalized_url_hash: link.normalized_url_hash.clone(),
+            active_from: link.active_from,
+            consumed_at: link.consumed_at,
+            deleted_at: link.deleted_at,
+            metadata: link.metadata.clone(),
+            broken_since: link.broken_since,
+            options: link.options.clone(),
+            health_failures: 0,
+            next_health_check_at: chrono::Utc::now(),
+        }
+    }
+}
+
+impl From<LinkRow> for Link {
+    fn from(row: LinkRow) -> Self {
+        Link::from_parts(
+            row.id,
+            row.user_id,
+            row.redirect_url,
+            row.label,
+            row.views,
+            row.created_at,
+            row.last_view,
+            row.normalized_url_hash,
+            row.active_from,
+            row.consumed_at,
+            row.deleted_at,
+            row.metadata,
+            row.broken_since,
+            row.options,
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct Tables {
+    links: Vec<LinkRow>,
+    country_views: HashMap<(String, String), i64>,
+    variant_views: HashMap<(String, String), i64>,
+    health_checks: Vec<(LinkId, HealthCheck)>,
+    idempotency_records: Vec<IdempotencyRecord>,
+}
+
+impl Tables {
+    fn link_mut(&mut self, link_id: &LinkId) -> Option<&mut LinkRow> {
+        self.links.iter_mut().find(|row| row.id == *link_id)
+    }
+}
+
+/// Link manager repo backed by plain collections, with the semantics of the Postgres repo.
+#[derive(Debug, Default)]
+pub struct InMemoryLinkManagerRepo {
+    tables: Mutex<Tables>,
+}
+
+impl InMemoryLinkManagerRepo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn tables(&self) -> std::sync::MutexGuard<'_, Tables> {
+        self.tables.lock().expect("links lock poisoned")
+    }
+}
+
+#[async_trait::async_trait]
+impl PersistenceRepo for InMemoryLinkManagerRepo {
+    async fn save_link(&self, link: Link, _ctx: TrxContext) -> Result<(), PersistenceError> {
+        let mut tables = self.tables();
+        let row = LinkRow::from(link);
+        match tables.link_mut(&row.id) {
+            Some(existing) => {
+                *existing = LinkRow {
+                    user_id: existing.user_id,
+                    created_at: existing.created_at,
+                    health_failures: existing.health_failures,
+                    next_health_check_at: existing.next_health_check_at,
+                    ..row
+                }
+            }
+            None => tables.links.push(row),
+        }
+
+        Ok(())
+    }
+
+    async fn insert_link(&self, link: Link, _ctx: TrxContext) -> Result<(), PersistenceError> {
+        let mut tables = self.tables();
+        let row = LinkRow::from(link);
+        if tables.link_mut(&row.id).is_some() {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "link {} already exists",
+                row.id
+            )));
+        }
+        tables.links.push(row);
+
+        Ok(())
+    }
+
+    async fn increment_link_views(
+        &self,
+        link_id: &LinkId,
+        _ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        if let Some(row) = self.tables().link_mut(link_id) {
+            row.views += 1;
+            row.last_view = Some(chrono::Utc::now());
+        }
+
+        Ok(())
+    }
+
+    async fn save_link_metadata(
+        &self,
+        link_id: &LinkId,
+        metadata: LinkMetadata,
+        _ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        if let Some(row) = self.tables().link_mut(link_id) {
+            if row.label.is_empty()
+                && let Some(title) = &metadata.title
+            {
+                row.label = title.clone();
+            }
+            row.metadata = Some(metadata);
+        }
+
+        Ok(())
+    }
+
+    async fn consume_link(
+        &self,
+        link_id: &LinkId,
+        _ctx: TrxContext,
+    ) -> Result<bool, PersistenceError> {
+        match self.tables().link_mut(link_id) {
+            Some(row) if row.consumed_at.is_none() => {
+                row.consumed_at = Some(chrono::Utc::now());
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn increment_country_views(
+        &self,
+        link_id: &LinkId,
+        country: &str,
+        _ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        *self
+            .tables()
+            .country_views
+            .entry((link_id.to_string(), country.to_string()))
+            .or_default() += 1;
+
+        Ok(())
+    }
+
+    async fn find_country_views(
+        &self,
+        link_id: &LinkId,
+        _ctx: TrxContext,
+    ) -> Result<Vec<CountryViews>, PersistenceError> {
+        let mut views: Vec<CountryViews> = self
+            .tables()
+            .country_views
+            .iter()
+            .filter(|((id, _), _)| *id == link_id.value)
+            .map(|((_, country), views)| CountryViews {
+                country: country.clone(),
+                views: *views,
+            })
+            .collect();
+        views.sort_by_key(|v| Reverse(v.views));
+
+        Ok(views)
+    }
+
+    async fn increment_variant_views(
+        &self,
+        link_id: &LinkId,
+        variant: &str,
+        _ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        *self
+            .tables()
+            .variant_views
+            .entry((link_id.to_string(), variant.to_string()))
+            .or_default() += 1;
+
+        Ok(())
+    }
+
+    async fn find_variant_views(
+        &self,
+        link_id: &LinkId,
+        _ctx: TrxContext,
+    ) -> Result<Vec<(String, i64)>, PersistenceError> {
+        Ok(self
+            .tables()
+            .variant_views
+            .iter()
+            .filter(|((id, _), _)| *id == link_id.value)
+            .map(|((_, variant), views)| (variant.clone(), *views))
+            .collect())
+    }
+
+    async fn next_link_id(&self, _ctx: TrxContext) -> Result<LinkId, PersistenceError> {
+        let mut tables = self.tables();
+        for _ in 0..MAX_LINK_ID_ATTEMPTS {
+            let link_id = LinkId::generate();
+            if tables.link_mut(&link_id).is_none() {
+                return Ok(link_id);
+            }
+        }
+
+        Err(PersistenceError::InternalError(eyre::eyre!(
+            "no free link id after {MAX_LINK_ID_ATTEMPTS} attempts"
+        )))
+    }
+
+    async fn find_all_link_ids(&self, _ctx: TrxContext) -> Result<Vec<LinkId>, PersistenceError> {
+        Ok(self
+            .tables()
+            .links
+            .iter()
+            .map(|row| row.id.clone())
+            .collect())
+    }
+
+    async fn find_link_by_id(
+        &self,
+        link_id: &LinkId,
+        _ctx: TrxContext,
+    ) -> Result<Option<Link>, PersistenceError> {
+        Ok(self.tables().link_mut(link_id).cloned().map(Link::from))
+    }
+
+    async fn find_links_by_normalized_url_hash(
+        &self,
+        user_id: i32,
+        normalized_url_hash: &str,
+        _ctx: TrxContext,
+    ) -> Result<Vec<Link>, PersistenceError> {
+        let mut rows: Vec<_> = self
+            .tables()
+            .links
+            .iter()
+            .filter(|row| {
+                row.user_id == user_id
+                    && row.normalized_url_hash.as_deref() == Some(normalized_url_hash)
+                    && row.deleted_at.is_none()
+                    && row.consumed_at.is_none()
+            })
+            .cloned()
+            .collect();
+        rows.sort_by_key(|row| row.created_at);
+
+        Ok(rows.into_iter().map(Link::from).collect())
+    }
+
+    async fn find_links_by_user_id(
+        &self,
+        user_id: i32,
+        _ctx: TrxContext,
+    ) -> Result<Vec<Link>, PersistenceError> {
+        let mut rows: Vec<LinkRow> = self
+            .tables()
+            .links
+            .iter()
+            .filter(|row| row.user_id == user_id && row.deleted_at.is_none())
+            .cloned()
+            .collect();
+        rows.sort_by_key(|row| row.created_at);
+
+        Ok(rows.into_iter().map(Link::from).collect())
+    }
+
+    async fn delete_link(&self, link_id: LinkId, _ctx: TrxContext) -> Result<(), PersistenceError> {
+        if let Some(row) = self.tables().link_mut(&link_id)
+            && row.deleted_at.is_none()
+        {
+            row.deleted_at = Some(chrono::Utc::now());
+        }
+
+        Ok(())
+    }
+
+    async fn restore_link(
+        &self,
+        link_id: &LinkId,
+        _ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        if let Some(row) = self.tables().link_mut(link_id) {
+            row.deleted_at = None;
+        }
+
+        Ok(())
+    }
+
+    async fn find_deleted_links_by_user_id(
+        &self,
+        user_id: i32,
+        _ctx: TrxContext,
+    ) -> Result<Vec<Link>, PersistenceError> {
+        let mut rows: Vec<LinkRow> = self
+            .tables()
+            .links
+            .iter()
+            .filter(|row| row.user_id == user_id && row.deleted_at.is_some())
+            .cloned()
+            .collect();
+        rows.sort_by_key(|row| Reverse(row.deleted_at));
+
+        Ok(rows.into_iter().map(Link::from).collect())
+    }
+
+    async fn purge_deleted_links(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+        _ctx: TrxContext,
+    ) -> Result<Vec<LinkId>, PersistenceError> {
+        let mut tables = self.tables();
+        let (purged, kept): (Vec<LinkRow>, Vec<LinkRow>) = std::mem::take(&mut tables.links)
+            .into_iter()
+            .partition(|row| row.deleted_at.is_some_and(|at| at < deleted_before));
+        tables.links = kept;
+
+        let purged: Vec<LinkId> = purged.into_iter().map(|row| row.id).collect();
+        // the foreign keys cascade
+        tables
+            .country_views
+            .retain(|(id, _), _| purged.iter().all(|p| p.value != *id));
+        tables
+            .variant_views
+            .retain(|(id, _), _| purged.iter().all(|p| p.value != *id));
+        tables.health_checks.retain(|(id, _)| !purged.contains(id));
+        tables
+            .idempotency_records
+            .retain(|record| !purged.contains(&record.link_id));
+
+        Ok(purged)
+    }
+
+    async fn claim_links_due_for_health_check(
+        &self,
+        limit: i64,
+        lease_until: chrono::DateTime<chrono::Utc>,
+        _ctx: TrxContext,
+    ) -> Result<Vec<HealthCheckTarget>, PersistenceError> {
+        let now = chrono::Utc::now();
+        let mut tables = self.tables();
+        let mut due: Vec<&mut LinkRow> = tables
+            .links
+            .iter_mut()
+            .filter(|row| {
+                row.deleted_at.is_none()
+                    && row.consumed_at.is_none()
+                    && row.next_health_check_at <= now
+            })
+            .collect();
+        due.sort_by_key(|row| row.next_health_check_at);
+
+        Ok(due
+            .into_iter()
+            .take(limit.max(0) as usize)
+            .map(|row| {
+                row.next_health_check_at = lease_until;
+                HealthCheckTarget {
+                    link_id: row.id.clone(),
+                    url: row.redirect_url.clone(),
+                }
+            })
+            .collect())
+    }
+
+    async fn find_link_health_state(
+        &self,
+        link_id: &LinkId,
+        _ctx: TrxContext,
+    ) -> Result<Option<LinkHealthState>, PersistenceError> {
+        Ok(self.tables().link_mut(link_id).map(|row| LinkHealthState {
+            failures: row.health_failures,
+            broken_since: row.broken_since,
+        }))
+    }
+
+    async fn save_link_health_state(
+        &self,
+        link_id: &LinkId,
+        state: &LinkHealthState,
+        next_check_at: chrono::DateTime<chrono::Utc>,
+        _ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        if let Some(row) = self.tables().link_mut(link_id) {
+            row.health_failures = state.failures;
+            row.broken_since = state.broken_since;
+            row.next_health_check_at = next_check_at;
+        }
+
+        Ok(())
+    }
+
+    async fn save_health_check(
+        &self,
+        link_id: &LinkId,
+        check: &HealthCheck,
+        _ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        self.tables()
+            .health_checks
+            .push((link_id.clone(), check.clone()));
+
+        Ok(())
+    }
+
+    async fn find_health_checks(
+        &self,
+        link_id: &LinkId,
+        limit: i64,
+        _ctx: TrxContext,
+    ) -> Result<Vec<HealthCheck>, PersistenceError> {
+        let mut checks: Vec<HealthCheck> = self
+            .tables()
+            .health_checks
+            .iter()
+            .filter(|(id, _)| id == link_id)
+            .map(|(_, check)| check.clone())
+            .collect();
+        checks.sort_by_key(|check| Reverse(check.checked_at));
+        checks.truncate(limit.max(0) as usize);
+
+        Ok(checks)
+    }
+
+    async fn find_link_health_by_user_id(
+        &self,
+        user_id: i32,
+        _ctx: TrxContext,
+    ) -> Result<Vec<LinkHealth>, PersistenceError> {
+        let tables = self.tables();
+        let mut rows: Vec<&LinkRow> = tables
+            .links
+            .iter()
+            .filter(|row| row.user_id == user_id && row.deleted_at.is_none())
+            .collect();
+        // broken_since DESC NULLS LAST, health_failures DESC, created_at
+        rows.sort_by_key(|row| {
+            (
+                row.broken_since.is_none(),
+                Reverse(row.broken_since),
+                Reverse(row.health_failures),
+                row.created_at,
+            )
+        });
+
+        Ok(rows
+            .into_iter()
+            .map(|row| LinkHealth {
+                link_id: row.id.clone(),
+                redirect_url: row.redirect_url.clone(),
+                label: row.label.clone(),
+                state: LinkHealthState {
+                    failures: row.health_failures,
+                    broken_since: row.broken_since,
+                },
+                last_check: tables
+                    .health_checks
+                    .iter()
+                    .filter(|(id, _)| *id == row.id)
+                    .map(|(_, check)| check)
+                    .max_by_key(|check| check.checked_at)
+                    .cloned(),
+            })
+            .collect())
+    }
+
+    async fn delete_health_checks_before(
+        &self,
+        checked_before: chrono::DateTime<chrono::Utc>,
+        _ctx: TrxContext,
+    ) -> Result<u64, PersistenceError> {
+        let mut tables = self.tables();
+        let before = tables.health_checks.len();
+        tables
+            .health_checks
+            .retain(|(_, check)| check.checked_at >= checked_before);
+
+        Ok((before - tables.health_checks.len()) as u64)
+    }
+
+    async fn save_idempotency_record(
+        &self,
+        record: IdempotencyRecord,
+        _ctx: TrxContext,
+    ) -> Result<bool, PersistenceError> {
+        let now = chrono::Utc::now();
+        let mut tables = self.tables();
+        tables.idempotency_records.retain(|existing| {
+            existing.user_id != record.user_id
+                || existing.key != record.key
+                || existing.expires_at >= now
+        });
+        if tables
+            .idempotency_records
+            .iter()
+            .any(|existing| existing.user_id == record.user_id && existing.key == record.key)
+        {
+            return Ok(false);
+        }
+
+        tables.idempotency_records.push(record);
+        Ok(true)
+    }
+
+    async fn find_idempotency_record(
+        &self,
+        user_id: i32,
+        key: &str,
+        _ctx: TrxContext,
+    ) -> Result<Option<IdempotencyRecord>, PersistenceError> {
+        Ok(self
+            .tables()
+            .idempotency_records
+            .iter()
+            .find(|record| record.user_id == user_id && record.key == key)
+            .cloned())
+    }
+
+    async fn delete_expired_idempotency_records(
+        &self,
+        expired_before: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+        _ctx: TrxContext,
+    ) -> Result<u64, PersistenceError> {
+        let mut tables = self.tables();
+        let mut remaining = limit;
+        tables.idempotency_records.retain(|record| {
+            if remaining > 0 && record.expires_at < expired_before {
+                remaining -= 1;
+                return false;
+            }
+            true
+        });
+
+        Ok((limit - remaining) as u64)
+    }
+}
+
+struct CacheEntry {
+    link: Option<Link>,
+    expires_at: Instant,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    versions: HashMap<String, u64>,
+}
+
+/// Link cache with the versioning of `RedisLinkCache`. Without `available` every call is
+/// bypassed, like a Redis that is down.
+pub struct InMemoryLinkCache {
+    state: Mutex<CacheState>,
+    available: bool,
+}
+
+impl InMemoryLinkCache {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(CacheState::default()),
+            available: true,
+        }
+    }
+
+    pub fn unavailable() -> Self {
+        Self {
+            state: Mutex::new(CacheState::default()),
+            available: false,
+        }
+    }
+
+    /// What a lookup would find, without counting as one.
+    pub fn cached(&self, link_id: &LinkId) -> Option<Option<Link>> {
+        let state = self.state.lock().expect("cache lock poisoned");
+        state
+            .entries
+            .get(&link_id.value)
+            .filter(|entry| entry.expires_at > Instant::now())
+            .map(|entry| entry.link.clone())
+    }
+}
+
+impl Default for InMemoryLinkCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl LinkCache for InMemoryLinkCache {
+    async fn get(&self, link_id: &LinkId) -> CacheLookup {
+        if !self.available {
+            return CacheLookup::Unavailable;
+        }
+
+        let version = self
+            .state
+            .lock()
+            .expect("cache lock poisoned")
+            .versions
+            .get(&link_id.value)
+            .copied()
+            .unwrap_or(0);
+        match self.cached(link_id) {
+            Some(link) => CacheLookup::Hit(link),
+            None => CacheLookup::Miss(version),
+        }
+    }
+
+    async fn put(&self, link_id: &LinkId, link: Option<&Link>, ttl_sec: u64, version: u64) {
+        if !self.available {
+            return;
+        }
+
+        let mut state = self.state.lock().expect("cache lock poisoned");
+        if state.versions.get(&link_id.value).copied().unwrap_or(0) != version {
+            return;
+        }
+        state.entries.insert(
+            link_id.value.clone(),
+            CacheEntry {
+                link: link.cloned(),
+                expires_at: Instant::now() + Duration::from_secs(ttl_sec),
+            },
+        );
+    }
+
+    async fn invalidate(&self, link_id: &LinkId) {
+        if !self.available {
+            return;
+        }
+
+        let mut state = self.state.lock().expect("cache lock poisoned");
+        *state.versions.entry(link_id.value.clone()).or_default() += 1;
+        state.entries.remove(&link_id.value);
+    }
+
+    fn status(&self) -> RedisStatus {
+        if self.available {
+            RedisStatus::Up
+        } else {
+            RedisStatus::Disconnected
+        }
+    }
+
+    fn bypassed(&self) -> u64 {
+        0
+    }
+}
@@ -0,0 +1,382 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use redis::Script;
+
+use crate::{
+    config::CacheConfig,
+    domain::link_manager::{
+        entity::link::{Link, LinkId},
+        service::{CacheLookup, LinkCache},
+    },
+    tools::{
+        failure_log::FailureLog,
+        redis_connection::{RedisConnection, RedisStatus},
+    },
+};
+
+/// Part of every key. Bump it whenever the serialized `Link` changes incompatibly, entries
+/// written by older releases are then never read again and simply expire.
+const SCHEMA_VERSION: u32 = 1;
+/// Outlives every entry, a version that expires early could let a stale `put` through.
+const VERSION_TTL_SEC: i64 = 86400;
+/// Beyond this many invalidations missed while Redis was bypassed, every entry is dropped
+/// on recovery instead.
+const MAX_PENDING_INVALIDATIONS: usize = 100_000;
+/// Invalidations sent or replayed per round trip.
+const INVALIDATION_BATCH_SIZE: usize = 500;
+/// Keys deleted per round trip when every entry is dropped.
+const FLUSH_SCAN_COUNT: usize = 1000;
+
+/// Writes the entry only if the version is still the one read before the database lookup.
+const PUT_SCRIPT: &str = r"
+if (redis.call('GET', KEYS[2]) or '0') ~= ARGV[1] then
+    return 0
+end
+redis.call('SET', KEYS[1], ARGV[2], 'EX', ARGV[3])
+return 1
+";
+
+/// Link cache in Redis. Each link has an entry and a version, both in the same hash slot.
+/// Invalidation bumps the version and deletes the entry, so a lookup that read the
+/// database before the change can not write its stale result back afterwards.
+///
+/// Invalidations that could not be sent are kept and replayed by
+/// `replay_pending_invalidations`, until then their links are read from the database.
+pub struct RedisLinkCache {
+    redis: Arc<RedisConnection>,
+    key_prefix: String,
+    invalidation_channel: String,
+    put_script: Script,
+    pending_invalidations: Mutex<HashSet<LinkId>>,
+    /// Set when more invalidations were missed than could be kept.
+    pending_overflowed: AtomicBool,
+    unreadable_entries: FailureLog,
+}
+
+impl RedisLinkCache {
+    pub fn new(redis: Arc<RedisConnection>, cache_config: &CacheConfig) -> Self {
+        Self {
+            redis,
+            key_prefix: cache_config.key_prefix.clone(),
+            invalidation_channel: cache_config.invalidation_channel.clone(),
+            put_script: Script::new(PUT_SCRIPT),
+            pending_invalidations: Mutex::new(HashSet::new()),
+            pending_overflowed: AtomicBool::new(false),
+            unreadable_entries: FailureLog::new("read cache entries, treating them as misses"),
+        }
+    }
+
+    fn is_pending(&self, link_id: &LinkId) -> bool {
+        if self.pending_overflowed.load(Ordering::Acquire) {
+            return true;
+        }
+        self.pending_invalidations
+            .lock()
+            .expect("pending invalidations lock poisoned")
+            .contains(link_id)
+    }
+
+    fn keep_pending(&self, link_ids: impl IntoIterator<Item = LinkId>) {
+        let mut pending = self
+            .pending_invalidations
+            .lock()
+            .expect("pending invalidations lock poisoned");
+        for link_id in link_ids {
+            if pending.len() >= MAX_PENDING_INVALIDATIONS {
+                pending.clear();
+                self.pending_overflowed.store(true, Ordering::Release);
+                return;
+            }
+            pending.insert(link_id);
+        }
+    }
+
+    /// Bumps the versions, deletes the entries and tells the other instances, in one round
+    /// trip. `false` if Redis was bypassed or failed.
+    async fn send_invalidations(&self, link_ids: &[LinkId]) -> bool {
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for link_id in link_ids {
+            let version_key = self.version_key(link_id);
+            pipe.incr(&version_key, 1)
+                .ignore()
+                .expire(&version_key, VERSION_TTL_SEC)
+                .ignore()
+                .del(self.entry_key(link_id))
+                .ignore()
+                .publish(&self.invalidation_channel, link_id.to_string())
+                .ignore();
+        }
+
+        self.redis
+            .run(move |mut r| async move { pipe.query_async::<()>(&mut r).await })
+            .await
+            .is_some()
+    }
+
+    /// Sends the invalidations missed while Redis was bypassed. Entries are dropped
+    /// wholesale if too many were missed, other instances' local tiers then expire on
+    /// their own. Returns how many invalidations are still pending.
+    pub async fn replay_pending_invalidations(&self) -> usize {
+        if self.pending_overflowed.load(Ordering::Acquire) {
+            if !self.drop_all_entries().await {
+                return MAX_PENDING_INVALIDATIONS;
+            }
+            self.pending_overflowed.store(false, Ordering::Release);
+        }
+
+        let pending: Vec<LinkId> = self
+            .pending_invalidations
+            .lock()
+            .expect("pending invalidations lock poisoned")
+            .iter()
+            .cloned()
+            .collect();
+        for batch in pending.chunks(INVALIDATION_BATCH_SIZE) {
+            if !self.send_invalidations(batch).await {
+                break;
+            }
+            let mut pending = self
+                .pending_invalidations
+                .lock()
+                .expect("pending invalidations lock poisoned");
+            for link_id in batch {
+                pending.remove(link_id);
+            }
+        }
+
+        self.pending_invalidations
+            .lock()
+            .expect("pending invalidations lock poisoned")
+            .len()
+    }
+
+    /// Deletes every entry and version of the current schema. A `put` that read its
+    /// version before then no longer matches and is dropped.
+    ///
+    /// SCAN only walks the server the connection points at, so this assumes a single node.
+    /// Under Redis Cluster, entries on the other nodes would be kept until they expire.
+    async fn drop_all_entries(&self) -> bool {
+        let pattern = format!("{}:link:v{SCHEMA_VERSION}:*", self.key_prefix);
+        self.redis
+            .run(move |mut r| async move {
+                let mut cursor = 0u64;
+                loop {
+                    let (next, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+                        .arg(cursor)
+                        .arg("MATCH")
+                        .arg(&pattern)
+                        .arg("COUNT")
+                        .arg(FLUSH_SCAN_COUNT)
+                        .query_async(&mut r)
+                        .await?;
+                    if !keys.is_empty() {
+                        redis::cmd("UNLINK")
+                            .arg(keys)
+                            .query_async::<()>(&mut r)
+                            .await?;
+                    }
+                    if next == 0 {
+                        return Ok(());
+                    }
+                    cursor = next;
+                }
+            })
+            .await
+            .is_some()
+    }
+
+    fn entry_key(&self, link_id: &LinkId) -> String {
+        format!("{}:link:v{SCHEMA_VERSION}:{{{link_id}}}", self.key_prefix)
+    }
+
+    fn version_key(&self, link_id: &LinkId) -> String {
+        format!(
+            "{}:link:v{SCHEMA_VERSION}:{{{link_id}}}:version",
+            self.key_prefix
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl LinkCache for RedisLinkCache {
+    async fn get(&self, link_id: &LinkId) -> CacheLookup {
+        // the entry may predate a change whose invalidation is still pending
+        if self.is_pending(link_id) {
+            return CacheLookup::Unavailable;
+        }
+
+        let entry_key = self.entry_key(link_id);
+        let version_key = self.version_key(link_id);
+        let Some((entry, version)) = self
+            .redis
+            .run(move |mut r| async move {
+                redis::pipe()
+                    .get(entry_key)
+                    .get(version_key)
+                    .query_async::<(Option<String>, Option<u64>)>(&mut r)
+                    .await
+            })
+            .await
+        else {
+            return CacheLookup::Unavailable;
+        };
+
+        let version = version.unwrap_or(0);
+        let Some(entry) = entry else {
+            return CacheLookup::Miss(version);
+        };
+        match serde_json::from_str(&entry) {
+            Ok(link) => {
+                self.unreadable_entries.succeeded();
+                CacheLookup::Hit(link)
+            }
+            Err(e) => {
+                self.unreadable_entries.failed(e);
+                CacheLookup::Miss(version)
+            }
+        }
+    }
+
+    async fn put(&self, link_id: &LinkId, link: Option<&Link>, ttl_sec: u64, version: u64) {
+        let Ok(serialized) = serde_json::to_string(&link) else {
+            return;
+        };
+
+        let entry_key = self.entry_key(link_id);
+        let version_key = self.version_key(link_id);
+        let put_script = self.put_script.clone();
+        self.redis
+            .run(move |mut r| async move {
+                put_script
+                    .key(entry_key)
+                    .key(version_key)
+                    .arg(version)
+                    .arg(serialized)
+                    .arg(ttl_sec)
+                    .invoke_async::<()>(&mut r)
+                    .await
+            })
+            .await;
+    }
+
+    async fn invalidate(&self, link_id: &LinkId) {
+        let link_ids = [link_id.clone()];
+        if !self.send_invalidations(&link_ids).await {
+            self.keep_pending(link_ids);
+        }
+    }
+
+    async fn invalidate_many(&self, link_ids: &[LinkId]) {
+        for batch in link_ids.chunks(INVALIDATION_BATCH_SIZE) {
+            if !self.send_invalidations(batch).await {
+                self.keep_pending(batch.iter().cloned());
+            }
+        }
+    }
+
+    fn status(&self) -> RedisStatus {
+        self.redis.status()
+    }
+
+    fn bypassed(&self) -> u64 {
+        self.redis.bypassed()
+    }
+}
+
+/// Used when Redis is not configured. Every lookup goes to the local cache tier, if any,
+/// and then to the database.
+pub struct NoLinkCache;
+
+#[async_trait::async_trait]
+impl LinkCache for NoLinkCache {
+    async fn get(&self, _link_id: &LinkId) -> CacheLookup {
+        CacheLookup::Unavailable
+    }
+
+    async fn put(&self, _link_id: &LinkId, _link: Option<&Link>, _ttl_sec: u64, _version: u64) {}
+
+    async fn invalidate(&self, _link_id: &LinkId) {}
+
+    fn status(&self) -> RedisStatus {
+        RedisStatus::Disabled
+    }
+
+    fn bypassed(&self) -> u64 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RedisConfig;
+
+    /// Nothing listens on port 1, every command is skipped.
+    const UNREACHABLE_REDIS_URL: &str = "redis://127.0.0.1:1/";
+
+    async fn unreachable_cache() -> RedisLinkCache {
+        let redis_config = RedisConfig {
+            url: UNREACHABLE_REDIS_URL.to_string(),
+            connect_timeout_ms: 100,
+            response_timeout_ms: 100,
+            failure_threshold: 1,
+            open_sec: 1,
+            reconnect_interval_sec: 1,
+        };
+        let redis_client = redis::Client::open(UNREACHABLE_REDIS_URL).unwrap();
+        let redis = RedisConnection::connect(redis_client, &redis_config).await;
+
+        RedisLinkCache::new(Arc::new(redis), &CacheConfig::default())
+    }
+
+    #[tokio::test]
+    async fn missed_invalidation_is_kept_until_replayed() {
+        let cache = unreachable_cache().await;
+        let link_id = LinkId::from_string("abc".to_string());
+
+        cache.invalidate(&link_id).await;
+
+        assert!(matches!(
+            cache.get(&link_id).await,
+            CacheLookup::Unavailable
+        ));
+        assert_eq!(cache.replay_pending_invalidations().await, 1);
+    }
+
+    #[tokio::test]
+    async fn missed_batch_invalidations_are_kept_until_replayed() {
+        let cache = unreachable_cache().await;
+        let link_ids: Vec<LinkId> = (0..=INVALIDATION_BATCH_SIZE)
+            .map(|i| LinkId::from_string(i.to_string()))
+            .collect();
+
+        cache.invalidate_many(&link_ids).await;
+
+        for link_id in &link_ids {
+            assert!(matches!(cache.get(link_id).await, CacheLookup::Unavailable));
+        }
+        assert_eq!(cache.replay_pending_invalidations().await, link_ids.len());
+    }
+
+    #[tokio::test]
+    async fn too_many_missed_invalidations_bypass_every_entry() {
+        let cache = unreachable_cache().await;
+        cache.keep_pending(
+            (0..=MAX_PENDING_INVALIDATIONS).map(|i| LinkId::from_string(i.to_string())),
+        );
+
+        let other_link_id = LinkId::from_string("other".to_string());
+        assert!(cache.is_pending(&other_link_id));
+        assert_eq!(
+            cache.replay_pending_invalidations().await,
+            MAX_PENDING_INVALIDATIONS
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use reqwest::{
+    Client, Url,
+    dns::{Addrs, Name, Resolve, Resolving},
+    header, redirect,
+};
+
+use crate::{
+    config::MetadataConfig, domain::link_manager::entity::link_metadata::LinkMetadata,
+    tools::html_meta::extract_page_metadata,
+};
+
+const USER_AGENT: &str = "short-link-metadata-fetcher/0.1";
+
+#[derive(thiserror::Error, Debug)]
+pub enum MetadataFetchError {
+    #[error("invalid url: {0}")]
+    InvalidUrl(String),
+    #[error("blocked address: {0}")]
+    BlockedAddress(String),
+    #[error("request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("unexpected status: {0}")]
+    Status(u16),
+    #[error("not an html page: {0}")]
+    NotHtml(String),
+}
+
+/// Addresses the fetcher must never reach, so user supplied urls can not be used to probe
+/// the internal network.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip.to_canonical() {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                // shared address space, 100.64.0.0/10
+                || (a == 100 && (b & 0xc0) == 64)
+                // benchmarking, 198.18.0.0/15
+                || (a == 198 && (b & 0xfe) == 18)
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // unique local, fc00::/7
+                || (first & 0xfe00) == 0xfc00
+                // link local, fe80::/10
+                || (first & 0xffc0) == 0xfe80
+                // documentation, 2001:db8::/32
+                || (first == 0x2001 && ip.segments()[1] == 0x0db8))
+        }
+    }
+}
+
+/// Resolves hosts and drops every non-public address. The client connects to exactly the
+/// addresses returned here, so a host can not resolve to a public address for the check and
+/// to a private one for the connection.
+pub(super) struct PublicOnlyResolver {
+    pub(super) allow_private_networks: bool,
+}
+
+impl Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let allow_private_networks = self.allow_private_networks;
+        let host = name.as_str().to_string();
+
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| allow_private_networks || is_public_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(MetadataFetchError::BlockedAddress(host).into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Hosts given as an ip literal never reach the resolver and are checked here.
+pub(super) fn check_url(url: &Url, allow_private_networks: bool) -> Result<(), MetadataFetchError> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(MetadataFetchError::InvalidUrl(url.to_string()));
+    }
+
+    let ip = match url.host() {
+        Some(url::Host::Ipv4(ip)) => IpAddr::V4(ip),
+        Some(url::Host::Ipv6(ip)) => IpAddr::V6(ip),
+        Some(url::Host::Domain(_)) => return Ok(()),
+        None => return Err(MetadataFetchError::InvalidUrl(url.to_string())),
+    };
+    if !allow_private_networks && !is_public_ip(ip) {
+        return Err(MetadataFetchError::BlockedAddress(ip.to_string()));
+    }
+
+    Ok(())
+}
+
+pub struct MetadataFetcher {
+    client: Client,
+    max_body_bytes: usize,
+    allow_private_networks: bool,
+}
+
+impl MetadataFetcher {
+    pub fn new(config: &MetadataConfig) -> Result<Self, MetadataFetchError> {
+        let allow_private_networks = config.allow_private_networks;
+        let max_redirects = config.max_redirects;
+
+        let client = Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .connect_timeout(Duration::from_millis(config.timeout_ms))
+            .user_agent(USER_AGENT)
+            .no_proxy()
+            .dns_resolver(Arc::new(PublicOnlyResolver {
+                allow_private_networks,
+            }))
+            .redirect(redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() >= max_redirects {
+                    return attempt.error("too many redirects");
+                }
+                match check_url(attempt.url(), allow_private_networks) {
+                    Ok(()) => attempt.follow(),
+                    Err(e) => attempt.error(e),
+                }
+            }))
+            .build()?;
+
+        Ok(Self {
+            client,
+            max_body_bytes: config.max_body_bytes,
+            allow_private_networks,
+        })
+    }
+
+    pub async fn fetch(&self, url: &str) -> Result<LinkMetadata, MetadataFetchError> {
+        let url = Url::parse(url).map_err(|_| MetadataFetchError::InvalidUrl(url.to_string()))?;
+        check_url(&url, self.allow_private_networks)?;
+
+        let mut response = self
+            .client
+            .get(url)
+            .header(header::ACCEPT, "text/html,application/xhtml+xml")
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(MetadataFetchError::Status(response.status().as_u16()));
+        }
+
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        if !content_type.contains("html") {
+            return Err(MetadataFetchError::NotHtml(content_type));
+        }
+
+        let final_url = response.url().clone();
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            let remaining = self.max_body_bytes - body.len();
+            body.extend_from_slice(&chunk[..chunk.len().min(remaining)]);
+            if body.len() >= self.max_body_bytes {
+                break;
+            }
+        }
+
+        let html = String::from_utf8_lossy(&body);
+        Ok(extract_page_metadata(&html, &final_url).into())
+    }
+}
@@ -1 +1,8 @@
-pub mod persistence;
\ No newline at end of file
+pub mod health_checker;
+#[cfg(test)]
+pub mod in_memory;
+pub mod link_cache;
+pub mod metadata_fetcher;
+pub mod persistence;
+pub mod read_replicas;
+pub mod sqlite;
@@ -1,20 +1,64 @@
 use eyre::Context;
 use solar::trx_factory::{SqlxTrxFactory, TrxContext};
+use sqlx::PgExecutor;
+use sqlx::types::Json;
 
-use crate::domain::link_manager::entity::link::{Link, LinkId};
+use crate::domain::link_manager::entity::idempotency_key::IdempotencyRecord;
+use crate::domain::link_manager::entity::link::{Link, LinkId, MAX_LINK_ID_ATTEMPTS};
+use crate::domain::link_manager::entity::link_health::{
+    HealthCheck, HealthCheckTarget, HealthOutcome, LinkHealth, LinkHealthState,
+};
+use crate::domain::link_manager::entity::link_metadata::LinkMetadata;
+use crate::domain::link_manager::entity::link_options::LinkOptions;
+use crate::domain::link_manager::entity::link_stats::CountryViews;
 use crate::domain::link_manager::service::{PersistenceError, PersistenceRepo};
 
+use super::read_replicas::ReadReplicas;
+
 pub struct LinkManagerPersistenceRepo {
     trx_factory: SqlxTrxFactory,
+    read_replicas: Option<ReadReplicas>,
 }
 
 impl LinkManagerPersistenceRepo {
     pub fn new(trx_factory: SqlxTrxFactory) -> Self {
-        Self { trx_factory }
+        Self {
+            trx_factory,
+            read_replicas: None,
+        }
+    }
+
+    /// Serves link lookups and stats outside a transaction from the replicas.
+    pub fn with_read_replicas(mut self, read_replicas: ReadReplicas) -> Self {
+        self.read_replicas = Some(read_replicas);
+        self
+    }
+
+    /// Views may lag a little, so stats skip the lag guard.
+    fn stats_replica(&self, ctx: &TrxContext) -> Option<&sqlx::PgPool> {
+        if !matches!(ctx, TrxContext::Empty) {
+            return None;
+        }
+        self.read_replicas.as_ref()?.pool()
+    }
+
+    /// `None` if the replica read failed, the caller then reads the primary.
+    fn replica_result<T>(&self, result: Result<T, sqlx::Error>) -> Option<T> {
+        let failures = self.read_replicas.as_ref()?.failures();
+        match result {
+            Ok(value) => {
+                failures.succeeded();
+                Some(value)
+            }
+            Err(e) => {
+                failures.failed(e);
+                None
+            }
+        }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, sqlx::FromRow)]
 pub struct LinkDto {
     pub id: String,
     pub user_id: i32,
@@ -23,6 +67,13 @@ pub struct LinkDto {
     pub views: i64,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub last_view: Option<chrono::DateTime<chrono::Utc>>,
+    pub normalized_url_hash: Option<String>,
+    pub active_from: Option<chrono::DateTime<chrono::Utc>>,
+    pub consumed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub metadata: Option<Json<LinkMetadata>>,
+    pub broken_since: Option<chrono::DateTime<chrono::Utc>>,
+    pub options: Json<LinkOptions>,
 }
 
 impl From<Link> for LinkDto {
@@ -36,6 +87,13 @@ impl From<Link> for LinkDto {
             views: link.views,
             created_at: link.created_at,
             last_view: link.last_view,
+            normalized_url_hash: link.normalized_url_hash.clone(),
+            active_from: link.active_from,
+            consumed_at: link.consumed_at,
+            deleted_at: link.deleted_at,
+            metadata: link.metadata.clone().map(Json),
+            broken_since: link.broken_since,
+            options: Json(link.options.clone()),
         }
     }
 }
@@ -52,10 +110,65 @@ impl From<LinkDto> for Link {
             link.views,
             link.created_at,
             link.last_view,
+            link.normalized_url_hash,
+            link.active_from,
+            link.consumed_at,
+            link.deleted_at,
+            link.metadata.map(|metadata| metadata.0),
+            link.broken_since,
+            link.options.0,
+        )
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct IdempotencyRecordDto {
+    pub user_id: i32,
+    pub key: String,
+    pub fingerprint: String,
+    pub link_id: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<IdempotencyRecordDto> for IdempotencyRecord {
+    fn from(record: IdempotencyRecordDto) -> Self {
+        IdempotencyRecord::from_parts(
+            record.user_id,
+            record.key,
+            record.fingerprint,
+            LinkId::from_string(record.link_id),
+            record.created_at,
+            record.expires_at,
         )
     }
 }
 
+#[derive(Debug)]
+pub struct HealthCheckDto {
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+    pub outcome: String,
+    pub status_code: Option<i32>,
+    pub latency_ms: i64,
+    pub redirect_chain: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl From<HealthCheckDto> for HealthCheck {
+    fn from(check: HealthCheckDto) -> Self {
+        Self {
+            checked_at: check.checked_at,
+            outcome: check.outcome.parse().unwrap_or(HealthOutcome::Broken),
+            status_code: check
+                .status_code
+                .and_then(|status| u16::try_from(status).ok()),
+            latency_ms: check.latency_ms,
+            redirect_chain: check.redirect_chain,
+            error: check.error,
+        }
+    }
+}
+
 #[async_trait::async_trait]
 impl PersistenceRepo for LinkManagerPersistenceRepo {
     async fn save_link(&self, link: Link, ctx: TrxContext) -> Result<(), PersistenceError> {
@@ -71,13 +184,20 @@ impl PersistenceRepo for LinkManagerPersistenceRepo {
         let link_dto = LinkDto::from(link.clone());
         sqlx::query!(
             r#"
-            INSERT INTO links (id, user_id, redirect_url, label, views, created_at, last_view)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            INSERT INTO links (id, user_id, redirect_url, label, views, created_at, last_view, normalized_url_hash, active_from, consumed_at, deleted_at, metadata, broken_since, options)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
             ON CONFLICT (id) DO UPDATE SET
             redirect_url = EXCLUDED.redirect_url,
             label = EXCLUDED.label,
             views = EXCLUDED.views,
-            last_view = EXCLUDED.last_view
+            last_view = EXCLUDED.last_view,
+            normalized_url_hash = EXCLUDED.normalized_url_hash,
+            active_from = EXCLUDED.active_from,
+            consumed_at = EXCLUDED.consumed_at,
+            deleted_at = EXCLUDED.deleted_at,
+            metadata = EXCLUDED.metadata,
+            broken_since = EXCLUDED.broken_since,
+            options = EXCLUDED.options
             
             "#,
             link_dto.id,
@@ -86,7 +206,14 @@ impl PersistenceRepo for LinkManagerPersistenceRepo {
             link_dto.label,
             link_dto.views,
             link_dto.created_at,
-            link_dto.last_view
+            link_dto.last_view,
+            link_dto.normalized_url_hash,
+            link_dto.active_from,
+            link_dto.consumed_at,
+            link_dto.deleted_at,
+            link_dto.metadata as _,
+            link_dto.broken_since,
+            link_dto.options as _
         )
         .execute(&mut **trx)
         .await
@@ -95,9 +222,100 @@ impl PersistenceRepo for LinkManagerPersistenceRepo {
         Ok(())
     }
 
+    async fn insert_link(&self, link: Link, ctx: TrxContext) -> Result<(), PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let link_dto = LinkDto::from(link.clone());
+        sqlx::query!(
+            r#"
+            INSERT INTO links (id, user_id, redirect_url, label, views, created_at, last_view, normalized_url_hash, active_from, consumed_at, deleted_at, metadata, broken_since, options)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            "#,
+            link_dto.id,
+            link_dto.user_id,
+            link_dto.redirect_url,
+            link_dto.label,
+            link_dto.views,
+            link_dto.created_at,
+            link_dto.last_view,
+            link_dto.normalized_url_hash,
+            link_dto.active_from,
+            link_dto.consumed_at,
+            link_dto.deleted_at,
+            link_dto.metadata as _,
+            link_dto.broken_since,
+            link_dto.options as _
+        )
+        .execute(&mut **trx)
+        .await
+        .context("failed to insert link")?;
+
+        Ok(())
+    }
+
     async fn next_link_id(&self, ctx: TrxContext) -> Result<LinkId, PersistenceError> {
-        let _ = ctx;
-        Ok(LinkId::generate())
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        for _ in 0..MAX_LINK_ID_ATTEMPTS {
+            let link_id = LinkId::generate();
+            let taken = sqlx::query_scalar!(
+                r#"
+                SELECT EXISTS(SELECT 1 FROM links WHERE id = $1) AS "taken!"
+                "#,
+                link_id.to_string()
+            )
+            .fetch_one(&mut **trx)
+            .await
+            .context("failed to check link id")?;
+
+            if !taken {
+                return Ok(link_id);
+            }
+        }
+
+        Err(PersistenceError::InternalError(eyre::eyre!(
+            "no free link id after {MAX_LINK_ID_ATTEMPTS} attempts"
+        )))
+    }
+
+    async fn find_all_link_ids(&self, ctx: TrxContext) -> Result<Vec<LinkId>, PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT id
+            FROM links
+            "#
+        )
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to find link ids")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| LinkId::from_string(row.id))
+            .collect())
     }
 
     async fn find_link_by_id(
@@ -105,6 +323,43 @@ impl PersistenceRepo for LinkManagerPersistenceRepo {
         link_id: &LinkId,
         ctx: TrxContext,
     ) -> Result<Option<Link>, PersistenceError> {
+        if matches!(ctx, TrxContext::Empty)
+            && let Some(replica) = self
+                .read_replicas
+                .as_ref()
+                .and_then(|read_replicas| read_replicas.pool_for_link(link_id))
+        {
+            // a link the replica does not have yet may still be on the primary, and a miss
+            // would be cached as a negative entry
+            if let Some(Some(link_dto)) =
+                self.replica_result(fetch_link_by_id(replica, link_id).await)
+            {
+                return Ok(Some(Link::from(link_dto)));
+            }
+        }
+
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let link_dto = fetch_link_by_id(&mut **trx, link_id)
+            .await
+            .context("failed to find link by id")?;
+
+        Ok(link_dto.map(Link::from))
+    }
+
+    async fn find_links_by_normalized_url_hash(
+        &self,
+        user_id: i32,
+        normalized_url_hash: &str,
+        ctx: TrxContext,
+    ) -> Result<Vec<Link>, PersistenceError> {
         let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
         let (trx, _) = extract_or_create_trx;
         let mut trx = trx.lock().await;
@@ -114,24 +369,58 @@ impl PersistenceRepo for LinkManagerPersistenceRepo {
             )));
         };
 
-        let link_dto = sqlx::query_as!(
+        let link_dtos = sqlx::query_as!(
             LinkDto,
             r#"
-            SELECT id, user_id, redirect_url, label, views, created_at, last_view
+            SELECT id, user_id, redirect_url, label, views, created_at, last_view, normalized_url_hash,
+                active_from, consumed_at, deleted_at, broken_since,
+                metadata as "metadata: Json<LinkMetadata>", options as "options: Json<LinkOptions>"
             FROM links
-            WHERE id = $1
+            WHERE user_id = $1 AND normalized_url_hash = $2
+                AND deleted_at IS NULL AND consumed_at IS NULL
+            ORDER BY created_at
             "#,
-            link_id.to_string()
+            user_id,
+            normalized_url_hash
         )
-        .fetch_optional(&mut **trx)
+        .fetch_all(&mut **trx)
         .await
-        .context("failed to find link by id")?;
+        .context("failed to find links by normalized url hash")?;
 
-        if let Some(link_dto) = link_dto {
-            return Ok(Some(Link::from(link_dto)));
-        }
+        Ok(link_dtos.into_iter().map(Link::from).collect())
+    }
+
+    async fn find_links_by_user_id(
+        &self,
+        user_id: i32,
+        ctx: TrxContext,
+    ) -> Result<Vec<Link>, PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let link_dtos = sqlx::query_as!(
+            LinkDto,
+            r#"
+            SELECT id, user_id, redirect_url, label, views, created_at, last_view, normalized_url_hash,
+                active_from, consumed_at, deleted_at, broken_since,
+                metadata as "metadata: Json<LinkMetadata>", options as "options: Json<LinkOptions>"
+            FROM links
+            WHERE user_id = $1 AND deleted_at IS NULL
+            ORDER BY created_at
+            "#,
+            user_id
+        )
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to find links by user id")?;
 
-        Ok(None)
+        Ok(link_dtos.into_iter().map(Link::from).collect())
     }
 
     async fn delete_link(&self, link_id: LinkId, ctx: TrxContext) -> Result<(), PersistenceError> {
@@ -146,8 +435,9 @@ impl PersistenceRepo for LinkManagerPersistenceRepo {
 
         sqlx::query!(
             r#"
-            DELETE FROM links
-            WHERE id = $1
+            UPDATE links
+            SET deleted_at = now()
+            WHERE id = $1 AND deleted_at IS NULL
             "#,
             link_id.to_string()
         )
@@ -158,6 +448,100 @@ impl PersistenceRepo for LinkManagerPersistenceRepo {
         Ok(())
     }
 
+    async fn restore_link(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE links
+            SET deleted_at = NULL
+            WHERE id = $1
+            "#,
+            link_id.to_string()
+        )
+        .execute(&mut **trx)
+        .await
+        .context("failed to restore link")?;
+
+        Ok(())
+    }
+
+    async fn find_deleted_links_by_user_id(
+        &self,
+        user_id: i32,
+        ctx: TrxContext,
+    ) -> Result<Vec<Link>, PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let link_dtos = sqlx::query_as!(
+            LinkDto,
+            r#"
+            SELECT id, user_id, redirect_url, label, views, created_at, last_view, normalized_url_hash,
+                active_from, consumed_at, deleted_at, broken_since,
+                metadata as "metadata: Json<LinkMetadata>", options as "options: Json<LinkOptions>"
+            FROM links
+            WHERE user_id = $1 AND deleted_at IS NOT NULL
+            ORDER BY deleted_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to find deleted links by user id")?;
+
+        Ok(link_dtos.into_iter().map(Link::from).collect())
+    }
+
+    async fn purge_deleted_links(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<Vec<LinkId>, PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let rows = sqlx::query!(
+            r#"
+            DELETE FROM links
+            WHERE deleted_at < $1
+            RETURNING id
+            "#,
+            deleted_before
+        )
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to purge deleted links")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| LinkId::from_string(row.id))
+            .collect())
+    }
+
     async fn increment_link_views(
         &self,
         link_id: &LinkId,
@@ -186,4 +570,641 @@ impl PersistenceRepo for LinkManagerPersistenceRepo {
 
         Ok(())
     }
+
+    async fn save_link_metadata(
+        &self,
+        link_id: &LinkId,
+        metadata: LinkMetadata,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let title = metadata.title.clone();
+        sqlx::query!(
+            r#"
+            UPDATE links
+            SET metadata = $2,
+            label = CASE WHEN label = '' THEN COALESCE($3, label) ELSE label END
+            WHERE id = $1
+            "#,
+            link_id.to_string(),
+            Json(metadata) as _,
+            title
+        )
+        .execute(&mut **trx)
+        .await
+        .context("failed to save link metadata")?;
+
+        Ok(())
+    }
+
+    async fn consume_link(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<bool, PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE links
+            SET consumed_at = now()
+            WHERE id = $1 AND consumed_at IS NULL
+            "#,
+            link_id.to_string()
+        )
+        .execute(&mut **trx)
+        .await
+        .context("failed to consume link")?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn save_idempotency_record(
+        &self,
+        record: IdempotencyRecord,
+        ctx: TrxContext,
+    ) -> Result<bool, PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        sqlx::query!(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE user_id = $1 AND key = $2 AND expires_at < NOW()
+            "#,
+            record.user_id,
+            record.key
+        )
+        .execute(&mut **trx)
+        .await
+        .context("failed to delete expired idempotency key")?;
+
+        // a concurrent request with the same key blocks here until the first one commits
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO idempotency_keys (user_id, key, fingerprint, link_id, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (user_id, key) DO NOTHING
+            "#,
+            record.user_id,
+            record.key,
+            record.fingerprint,
+            record.link_id.to_string(),
+            record.created_at,
+            record.expires_at
+        )
+        .execute(&mut **trx)
+        .await
+        .context("failed to save idempotency key")?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn find_idempotency_record(
+        &self,
+        user_id: i32,
+        key: &str,
+        ctx: TrxContext,
+    ) -> Result<Option<IdempotencyRecord>, PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let record_dto = sqlx::query_as!(
+            IdempotencyRecordDto,
+            r#"
+            SELECT user_id, key, fingerprint, link_id, created_at, expires_at
+            FROM idempotency_keys
+            WHERE user_id = $1 AND key = $2
+            "#,
+            user_id,
+            key
+        )
+        .fetch_optional(&mut **trx)
+        .await
+        .context("failed to find idempotency key")?;
+
+        Ok(record_dto.map(IdempotencyRecord::from))
+    }
+
+    async fn delete_expired_idempotency_records(
+        &self,
+        expired_before: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+        ctx: TrxContext,
+    ) -> Result<u64, PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE (user_id, key) IN (
+                SELECT user_id, key FROM idempotency_keys
+                WHERE expires_at < $1
+                LIMIT $2
+            )
+            "#,
+            expired_before,
+            limit
+        )
+        .execute(&mut **trx)
+        .await
+        .context("failed to delete expired idempotency keys")?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn increment_country_views(
+        &self,
+        link_id: &LinkId,
+        country: &str,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO link_country_views (link_id, country, views)
+            VALUES ($1, $2, 1)
+            ON CONFLICT (link_id, country) DO UPDATE SET
+            views = link_country_views.views + 1
+            "#,
+            link_id.to_string(),
+            country
+        )
+        .execute(&mut **trx)
+        .await
+        .context("failed to increment country views")?;
+
+        Ok(())
+    }
+
+    async fn find_country_views(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<Vec<CountryViews>, PersistenceError> {
+        if let Some(replica) = self.stats_replica(&ctx)
+            && let Some(views) = self.replica_result(fetch_country_views(replica, link_id).await)
+        {
+            return Ok(views);
+        }
+
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let views = fetch_country_views(&mut **trx, link_id)
+            .await
+            .context("failed to find country views")?;
+
+        Ok(views)
+    }
+
+    async fn increment_variant_views(
+        &self,
+        link_id: &LinkId,
+        variant: &str,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO link_variant_views (link_id, variant, views)
+            VALUES ($1, $2, 1)
+            ON CONFLICT (link_id, variant) DO UPDATE SET
+            views = link_variant_views.views + 1
+            "#,
+            link_id.to_string(),
+            variant
+        )
+        .execute(&mut **trx)
+        .await
+        .context("failed to increment variant views")?;
+
+        Ok(())
+    }
+
+    async fn find_variant_views(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<Vec<(String, i64)>, PersistenceError> {
+        if let Some(replica) = self.stats_replica(&ctx)
+            && let Some(views) = self.replica_result(fetch_variant_views(replica, link_id).await)
+        {
+            return Ok(views);
+        }
+
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let views = fetch_variant_views(&mut **trx, link_id)
+            .await
+            .context("failed to find variant views")?;
+
+        Ok(views)
+    }
+
+    async fn claim_links_due_for_health_check(
+        &self,
+        limit: i64,
+        lease_until: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<Vec<HealthCheckTarget>, PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let rows = sqlx::query!(
+            r#"
+            UPDATE links
+            SET next_health_check_at = $2
+            WHERE id IN (
+                SELECT id FROM links
+                WHERE deleted_at IS NULL AND consumed_at IS NULL AND next_health_check_at <= now()
+                ORDER BY next_health_check_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, redirect_url
+            "#,
+            limit,
+            lease_until
+        )
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to claim links for health check")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| HealthCheckTarget {
+                link_id: LinkId::from_string(row.id),
+                url: row.redirect_url,
+            })
+            .collect())
+    }
+
+    async fn find_link_health_state(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<Option<LinkHealthState>, PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let row = sqlx::query!(
+            r#"
+            SELECT health_failures, broken_since
+            FROM links
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            link_id.to_string()
+        )
+        .fetch_optional(&mut **trx)
+        .await
+        .context("failed to find link health state")?;
+
+        Ok(row.map(|row| LinkHealthState {
+            failures: row.health_failures,
+            broken_since: row.broken_since,
+        }))
+    }
+
+    async fn save_link_health_state(
+        &self,
+        link_id: &LinkId,
+        state: &LinkHealthState,
+        next_check_at: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE links
+            SET health_failures = $2, broken_since = $3, next_health_check_at = $4
+            WHERE id = $1
+            "#,
+            link_id.to_string(),
+            state.failures,
+            state.broken_since,
+            next_check_at
+        )
+        .execute(&mut **trx)
+        .await
+        .context("failed to save link health state")?;
+
+        Ok(())
+    }
+
+    async fn save_health_check(
+        &self,
+        link_id: &LinkId,
+        check: &HealthCheck,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO link_health_checks (link_id, checked_at, outcome, status_code, latency_ms, redirect_chain, error)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            link_id.to_string(),
+            check.checked_at,
+            check.outcome.as_str(),
+            check.status_code.map(i32::from),
+            check.latency_ms,
+            &check.redirect_chain,
+            check.error
+        )
+        .execute(&mut **trx)
+        .await
+        .context("failed to save health check")?;
+
+        Ok(())
+    }
+
+    async fn find_health_checks(
+        &self,
+        link_id: &LinkId,
+        limit: i64,
+        ctx: TrxContext,
+    ) -> Result<Vec<HealthCheck>, PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let checks = sqlx::query_as!(
+            HealthCheckDto,
+            r#"
+            SELECT checked_at, outcome, status_code, latency_ms, redirect_chain, error
+            FROM link_health_checks
+            WHERE link_id = $1
+            ORDER BY checked_at DESC
+            LIMIT $2
+            "#,
+            link_id.to_string(),
+            limit
+        )
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to find health checks")?;
+
+        Ok(checks.into_iter().map(HealthCheck::from).collect())
+    }
+
+    async fn find_link_health_by_user_id(
+        &self,
+        user_id: i32,
+        ctx: TrxContext,
+    ) -> Result<Vec<LinkHealth>, PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT l.id, l.redirect_url, l.label, l.health_failures, l.broken_since,
+                c.checked_at as "checked_at?", c.outcome as "outcome?", c.status_code,
+                c.latency_ms as "latency_ms?", c.redirect_chain as "redirect_chain?", c.error
+            FROM links l
+            LEFT JOIN LATERAL (
+                SELECT checked_at, outcome, status_code, latency_ms, redirect_chain, error
+                FROM link_health_checks
+                WHERE link_id = l.id
+                ORDER BY checked_at DESC
+                LIMIT 1
+            ) c ON true
+            WHERE l.user_id = $1 AND l.deleted_at IS NULL
+            ORDER BY l.broken_since DESC NULLS LAST, l.health_failures DESC, l.created_at
+            "#,
+            user_id
+        )
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to find link health by user id")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let last_check = match (row.checked_at, row.outcome, row.latency_ms) {
+                    (Some(checked_at), Some(outcome), Some(latency_ms)) => {
+                        Some(HealthCheck::from(HealthCheckDto {
+                            checked_at,
+                            outcome,
+                            status_code: row.status_code,
+                            latency_ms,
+                            redirect_chain: row.redirect_chain.unwrap_or_default(),
+                            error: row.error,
+                        }))
+                    }
+                    _ => None,
+                };
+
+                LinkHealth {
+                    link_id: LinkId::from_string(row.id),
+                    redirect_url: row.redirect_url,
+                    label: row.label,
+                    state: LinkHealthState {
+                        failures: row.health_failures,
+                        broken_since: row.broken_since,
+                    },
+                    last_check,
+                }
+            })
+            .collect())
+    }
+
+    async fn delete_health_checks_before(
+        &self,
+        checked_before: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<u64, PersistenceError> {
+        let extract_or_create_trx = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let (trx, _) = extract_or_create_trx;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM link_health_checks
+            WHERE checked_at < $1
+            "#,
+            checked_before
+        )
+        .execute(&mut **trx)
+        .await
+        .context("failed to delete health checks")?;
+
+        Ok(result.rows_affected())
+    }
+
+    fn note_link_modified(&self, link_id: &LinkId) {
+        if let Some(read_replicas) = &self.read_replicas {
+            read_replicas.note_modified(link_id);
+        }
+    }
+
+    fn reads_from_replicas(&self) -> bool {
+        self.read_replicas.is_some()
+    }
+}
+
+async fn fetch_link_by_id<'e>(
+    executor: impl PgExecutor<'e>,
+    link_id: &LinkId,
+) -> Result<Option<LinkDto>, sqlx::Error> {
+    sqlx::query_as!(
+        LinkDto,
+        r#"
+        SELECT id, user_id, redirect_url, label, views, created_at, last_view, normalized_url_hash,
+            active_from, consumed_at, deleted_at, broken_since,
+            metadata as "metadata: Json<LinkMetadata>", options as "options: Json<LinkOptions>"
+        FROM links
+        WHERE id = $1
+        "#,
+        link_id.to_string()
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+async fn fetch_country_views<'e>(
+    executor: impl PgExecutor<'e>,
+    link_id: &LinkId,
+) -> Result<Vec<CountryViews>, sqlx::Error> {
+    sqlx::query_as!(
+        CountryViews,
+        r#"
+        SELECT country, views
+        FROM link_country_views
+        WHERE link_id = $1
+        ORDER BY views DESC
+        "#,
+        link_id.to_string()
+    )
+    .fetch_all(executor)
+    .await
+}
+
+async fn fetch_variant_views<'e>(
+    executor: impl PgExecutor<'e>,
+    link_id: &LinkId,
+) -> Result<Vec<(String, i64)>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT variant, views
+        FROM link_variant_views
+        WHERE link_id = $1
+        "#,
+        link_id.to_string()
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.variant, row.views))
+        .collect())
 }
@@ -0,0 +1,61 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use moka::sync::Cache;
+use sqlx::PgPool;
+
+use crate::{domain::link_manager::entity::link::LinkId, tools::failure_log::FailureLog};
+
+/// Bounds the lag guard during bulk changes, like a trash purge.
+const RECENTLY_MODIFIED_CAPACITY: u64 = 100_000;
+
+/// Pools of the read replicas, used in turn. Links modified within the lag guard are read
+/// from the primary since the replicas may not have them yet.
+pub struct ReadReplicas {
+    pools: Vec<PgPool>,
+    next: AtomicUsize,
+    recently_modified: Cache<String, ()>,
+    failures: FailureLog,
+}
+
+impl ReadReplicas {
+    pub fn new(pools: Vec<PgPool>, lag_guard: Duration) -> Self {
+        Self {
+            pools,
+            next: AtomicUsize::new(0),
+            recently_modified: Cache::builder()
+                .max_capacity(RECENTLY_MODIFIED_CAPACITY)
+                .time_to_live(lag_guard)
+                .build(),
+            failures: FailureLog::new("read from replica, using primary"),
+        }
+    }
+
+    /// The next replica, `None` without replicas.
+    pub fn pool(&self) -> Option<&PgPool> {
+        if self.pools.is_empty() {
+            return None;
+        }
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.pools.len();
+        Some(&self.pools[index])
+    }
+
+    /// Like `pool`, but `None` while the replicas may still serve an older version of the link.
+    pub fn pool_for_link(&self, link_id: &LinkId) -> Option<&PgPool> {
+        if self.recently_modified.contains_key(&link_id.value) {
+            return None;
+        }
+        self.pool()
+    }
+
+    pub fn note_modified(&self, link_id: &LinkId) {
+        self.recently_modified.insert(link_id.value.clone(), ());
+    }
+
+    /// Failed reads fall back to the primary, an unreachable replica is logged once.
+    pub fn failures(&self) -> &FailureLog {
+        &self.failures
+    }
+}
@@ -0,0 +1,975 @@
+use chrono::Utc;
+use eyre::Context;
+use solar::trx_factory::TrxContext;
+use sqlx::types::Json;
+
+use crate::domain::link_manager::entity::idempotency_key::IdempotencyRecord;
+use crate::domain::link_manager::entity::link::{Link, LinkId, MAX_LINK_ID_ATTEMPTS};
+use crate::domain::link_manager::entity::link_health::{
+    HealthCheck, HealthCheckTarget, LinkHealth, LinkHealthState,
+};
+use crate::domain::link_manager::entity::link_metadata::LinkMetadata;
+use crate::domain::link_manager::entity::link_stats::CountryViews;
+use crate::domain::link_manager::service::{PersistenceError, PersistenceRepo};
+use crate::tools::sqlite_trx::SqliteTrxFactory;
+
+use super::persistence::{HealthCheckDto, IdempotencyRecordDto, LinkDto};
+
+/// Same semantics as `LinkManagerPersistenceRepo`. Row locks are not needed, every
+/// transaction holds the database write lock.
+pub struct LinkManagerSqliteRepo {
+    trx_factory: SqliteTrxFactory,
+}
+
+impl LinkManagerSqliteRepo {
+    pub fn new(trx_factory: SqliteTrxFactory) -> Self {
+        Self { trx_factory }
+    }
+}
+
+const SELECT_LINKS: &str = r#"
+    SELECT id, user_id, redirect_url, label, views, created_at, last_view, normalized_url_hash,
+        active_from, consumed_at, deleted_at, broken_since, metadata, options
+    FROM links
+"#;
+
+#[derive(Debug, sqlx::FromRow)]
+struct HealthCheckRow {
+    checked_at: chrono::DateTime<chrono::Utc>,
+    outcome: String,
+    status_code: Option<i32>,
+    latency_ms: i64,
+    redirect_chain: Json<Vec<String>>,
+    error: Option<String>,
+}
+
+impl From<HealthCheckRow> for HealthCheck {
+    fn from(check: HealthCheckRow) -> Self {
+        HealthCheck::from(HealthCheckDto {
+            checked_at: check.checked_at,
+            outcome: check.outcome,
+            status_code: check.status_code,
+            latency_ms: check.latency_ms,
+            redirect_chain: check.redirect_chain.0,
+            error: check.error,
+        })
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct LinkHealthRow {
+    id: String,
+    redirect_url: String,
+    label: String,
+    health_failures: i32,
+    broken_since: Option<chrono::DateTime<chrono::Utc>>,
+    checked_at: Option<chrono::DateTime<chrono::Utc>>,
+    outcome: Option<String>,
+    status_code: Option<i32>,
+    latency_ms: Option<i64>,
+    redirect_chain: Option<Json<Vec<String>>>,
+    error: Option<String>,
+}
+
+impl From<LinkHealthRow> for LinkHealth {
+    fn from(row: LinkHealthRow) -> Self {
+        let last_check = match (row.checked_at, row.outcome, row.latency_ms) {
+            (Some(checked_at), Some(outcome), Some(latency_ms)) => {
+                Some(HealthCheck::from(HealthCheckDto {
+                    checked_at,
+                    outcome,
+                    status_code: row.status_code,
+                    latency_ms,
+                    redirect_chain: row.redirect_chain.map(|chain| chain.0).unwrap_or_default(),
+                    error: row.error,
+                }))
+            }
+            _ => None,
+        };
+
+        LinkHealth {
+            link_id: LinkId::from_string(row.id),
+            redirect_url: row.redirect_url,
+            label: row.label,
+            state: LinkHealthState {
+                failures: row.health_failures,
+                broken_since: row.broken_since,
+            },
+            last_check,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl PersistenceRepo for LinkManagerSqliteRepo {
+    async fn save_link(&self, link: Link, ctx: TrxContext) -> Result<(), PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let link_dto = LinkDto::from(link);
+        sqlx::query(
+            r#"
+            INSERT INTO links (id, user_id, redirect_url, label, views, created_at, last_view, normalized_url_hash, active_from, consumed_at, deleted_at, metadata, broken_since, options)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            ON CONFLICT (id) DO UPDATE SET
+            redirect_url = excluded.redirect_url,
+            label = excluded.label,
+            views = excluded.views,
+            last_view = excluded.last_view,
+            normalized_url_hash = excluded.normalized_url_hash,
+            active_from = excluded.active_from,
+            consumed_at = excluded.consumed_at,
+            deleted_at = excluded.deleted_at,
+            metadata = excluded.metadata,
+            broken_since = excluded.broken_since,
+            options = excluded.options
+            "#,
+        )
+        .bind(link_dto.id)
+        .bind(link_dto.user_id)
+        .bind(link_dto.redirect_url)
+        .bind(link_dto.label)
+        .bind(link_dto.views)
+        .bind(link_dto.created_at)
+        .bind(link_dto.last_view)
+        .bind(link_dto.normalized_url_hash)
+        .bind(link_dto.active_from)
+        .bind(link_dto.consumed_at)
+        .bind(link_dto.deleted_at)
+        .bind(link_dto.metadata)
+        .bind(link_dto.broken_since)
+        .bind(link_dto.options)
+        .execute(&mut **trx)
+        .await
+        .context("failed to save link")?;
+
+        Ok(())
+    }
+
+    async fn insert_link(&self, link: Link, ctx: TrxContext) -> Result<(), PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let link_dto = LinkDto::from(link);
+        sqlx::query(
+            r#"
+            INSERT INTO links (id, user_id, redirect_url, label, views, created_at, last_view, normalized_url_hash, active_from, consumed_at, deleted_at, metadata, broken_since, options)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            "#,
+        )
+        .bind(link_dto.id)
+        .bind(link_dto.user_id)
+        .bind(link_dto.redirect_url)
+        .bind(link_dto.label)
+        .bind(link_dto.views)
+        .bind(link_dto.created_at)
+        .bind(link_dto.last_view)
+        .bind(link_dto.normalized_url_hash)
+        .bind(link_dto.active_from)
+        .bind(link_dto.consumed_at)
+        .bind(link_dto.deleted_at)
+        .bind(link_dto.metadata)
+        .bind(link_dto.broken_since)
+        .bind(link_dto.options)
+        .execute(&mut **trx)
+        .await
+        .context("failed to insert link")?;
+
+        Ok(())
+    }
+
+    async fn next_link_id(&self, ctx: TrxContext) -> Result<LinkId, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        for _ in 0..MAX_LINK_ID_ATTEMPTS {
+            let link_id = LinkId::generate();
+            let taken =
+                sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM links WHERE id = $1)")
+                    .bind(link_id.to_string())
+                    .fetch_one(&mut **trx)
+                    .await
+                    .context("failed to check link id")?;
+
+            if !taken {
+                return Ok(link_id);
+            }
+        }
+
+        Err(PersistenceError::InternalError(eyre::eyre!(
+            "no free link id after {MAX_LINK_ID_ATTEMPTS} attempts"
+        )))
+    }
+
+    async fn find_all_link_ids(&self, ctx: TrxContext) -> Result<Vec<LinkId>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let ids = sqlx::query_as::<_, (String,)>("SELECT id FROM links")
+            .fetch_all(&mut **trx)
+            .await
+            .context("failed to find link ids")?;
+
+        Ok(ids
+            .into_iter()
+            .map(|(id,)| LinkId::from_string(id))
+            .collect())
+    }
+
+    async fn find_link_by_id(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<Option<Link>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let link_dto = sqlx::query_as::<_, LinkDto>(&format!("{SELECT_LINKS} WHERE id = $1"))
+            .bind(link_id.to_string())
+            .fetch_optional(&mut **trx)
+            .await
+            .context("failed to find link by id")?;
+
+        Ok(link_dto.map(Link::from))
+    }
+
+    async fn find_links_by_normalized_url_hash(
+        &self,
+        user_id: i32,
+        normalized_url_hash: &str,
+        ctx: TrxContext,
+    ) -> Result<Vec<Link>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let link_dtos = sqlx::query_as::<_, LinkDto>(&format!(
+            r#"
+            {SELECT_LINKS}
+            WHERE user_id = $1 AND normalized_url_hash = $2
+                AND deleted_at IS NULL AND consumed_at IS NULL
+            ORDER BY created_at
+            "#
+        ))
+        .bind(user_id)
+        .bind(normalized_url_hash)
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to find links by normalized url hash")?;
+
+        Ok(link_dtos.into_iter().map(Link::from).collect())
+    }
+
+    async fn find_links_by_user_id(
+        &self,
+        user_id: i32,
+        ctx: TrxContext,
+    ) -> Result<Vec<Link>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let link_dtos = sqlx::query_as::<_, LinkDto>(&format!(
+            r#"
+            {SELECT_LINKS}
+            WHERE user_id = $1 AND deleted_at IS NULL
+            ORDER BY created_at
+            "#
+        ))
+        .bind(user_id)
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to find links by user id")?;
+
+        Ok(link_dtos.into_iter().map(Link::from).collect())
+    }
+
+    async fn delete_link(&self, link_id: LinkId, ctx: TrxContext) -> Result<(), PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE links
+            SET deleted_at = $2
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(link_id.to_string())
+        .bind(Utc::now())
+        .execute(&mut **trx)
+        .await
+        .context("failed to delete link")?;
+
+        Ok(())
+    }
+
+    async fn restore_link(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        sqlx::query("UPDATE links SET deleted_at = NULL WHERE id = $1")
+            .bind(link_id.to_string())
+            .execute(&mut **trx)
+            .await
+            .context("failed to restore link")?;
+
+        Ok(())
+    }
+
+    async fn find_deleted_links_by_user_id(
+        &self,
+        user_id: i32,
+        ctx: TrxContext,
+    ) -> Result<Vec<Link>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let link_dtos = sqlx::query_as::<_, LinkDto>(&format!(
+            r#"
+            {SELECT_LINKS}
+            WHERE user_id = $1 AND deleted_at IS NOT NULL
+            ORDER BY deleted_at DESC
+            "#
+        ))
+        .bind(user_id)
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to find deleted links by user id")?;
+
+        Ok(link_dtos.into_iter().map(Link::from).collect())
+    }
+
+    async fn purge_deleted_links(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<Vec<LinkId>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let ids = sqlx::query_as::<_, (String,)>(
+            r#"
+            DELETE FROM links
+            WHERE deleted_at < $1
+            RETURNING id
+            "#,
+        )
+        .bind(deleted_before)
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to purge deleted links")?;
+
+        Ok(ids
+            .into_iter()
+            .map(|(id,)| LinkId::from_string(id))
+            .collect())
+    }
+
+    async fn increment_link_views(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE links
+            SET views = views + 1, last_view = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(link_id.to_string())
+        .bind(Utc::now())
+        .execute(&mut **trx)
+        .await
+        .context("failed to increment views")?;
+
+        Ok(())
+    }
+
+    async fn save_link_metadata(
+        &self,
+        link_id: &LinkId,
+        metadata: LinkMetadata,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let title = metadata.title.clone();
+        sqlx::query(
+            r#"
+            UPDATE links
+            SET metadata = $2,
+            label = CASE WHEN label = '' THEN COALESCE($3, label) ELSE label END
+            WHERE id = $1
+            "#,
+        )
+        .bind(link_id.to_string())
+        .bind(Json(metadata))
+        .bind(title)
+        .execute(&mut **trx)
+        .await
+        .context("failed to save link metadata")?;
+
+        Ok(())
+    }
+
+    async fn consume_link(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<bool, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let result = sqlx::query(
+            r#"
+            UPDATE links
+            SET consumed_at = $2
+            WHERE id = $1 AND consumed_at IS NULL
+            "#,
+        )
+        .bind(link_id.to_string())
+        .bind(Utc::now())
+        .execute(&mut **trx)
+        .await
+        .context("failed to consume link")?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn save_idempotency_record(
+        &self,
+        record: IdempotencyRecord,
+        ctx: TrxContext,
+    ) -> Result<bool, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        sqlx::query(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE user_id = $1 AND key = $2 AND expires_at < $3
+            "#,
+        )
+        .bind(record.user_id)
+        .bind(&record.key)
+        .bind(Utc::now())
+        .execute(&mut **trx)
+        .await
+        .context("failed to delete expired idempotency key")?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO idempotency_keys (user_id, key, fingerprint, link_id, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (user_id, key) DO NOTHING
+            "#,
+        )
+        .bind(record.user_id)
+        .bind(&record.key)
+        .bind(&record.fingerprint)
+        .bind(record.link_id.to_string())
+        .bind(record.created_at)
+        .bind(record.expires_at)
+        .execute(&mut **trx)
+        .await
+        .context("failed to save idempotency key")?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn find_idempotency_record(
+        &self,
+        user_id: i32,
+        key: &str,
+        ctx: TrxContext,
+    ) -> Result<Option<IdempotencyRecord>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let record_dto = sqlx::query_as::<_, IdempotencyRecordDto>(
+            r#"
+            SELECT user_id, key, fingerprint, link_id, created_at, expires_at
+            FROM idempotency_keys
+            WHERE user_id = $1 AND key = $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(key)
+        .fetch_optional(&mut **trx)
+        .await
+        .context("failed to find idempotency key")?;
+
+        Ok(record_dto.map(IdempotencyRecord::from))
+    }
+
+    async fn delete_expired_idempotency_records(
+        &self,
+        expired_before: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+        ctx: TrxContext,
+    ) -> Result<u64, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let result = sqlx::query(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE rowid IN (
+                SELECT rowid FROM idempotency_keys
+                WHERE expires_at < $1
+                LIMIT $2
+            )
+            "#,
+        )
+        .bind(expired_before)
+        .bind(limit)
+        .execute(&mut **trx)
+        .await
+        .context("failed to delete expired idempotency keys")?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn increment_country_views(
+        &self,
+        link_id: &LinkId,
+        country: &str,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO link_country_views (link_id, country, views)
+            VALUES ($1, $2, 1)
+            ON CONFLICT (link_id, country) DO UPDATE SET
+            views = link_country_views.views + 1
+            "#,
+        )
+        .bind(link_id.to_string())
+        .bind(country)
+        .execute(&mut **trx)
+        .await
+        .context("failed to increment country views")?;
+
+        Ok(())
+    }
+
+    async fn find_country_views(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<Vec<CountryViews>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let rows = sqlx::query_as::<_, (String, i64)>(
+            r#"
+            SELECT country, views
+            FROM link_country_views
+            WHERE link_id = $1
+            ORDER BY views DESC
+            "#,
+        )
+        .bind(link_id.to_string())
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to find country views")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(country, views)| CountryViews { country, views })
+            .collect())
+    }
+
+    async fn increment_variant_views(
+        &self,
+        link_id: &LinkId,
+        variant: &str,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO link_variant_views (link_id, variant, views)
+            VALUES ($1, $2, 1)
+            ON CONFLICT (link_id, variant) DO UPDATE SET
+            views = link_variant_views.views + 1
+            "#,
+        )
+        .bind(link_id.to_string())
+        .bind(variant)
+        .execute(&mut **trx)
+        .await
+        .context("failed to increment variant views")?;
+
+        Ok(())
+    }
+
+    async fn find_variant_views(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<Vec<(String, i64)>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let rows = sqlx::query_as::<_, (String, i64)>(
+            r#"
+            SELECT variant, views
+            FROM link_variant_views
+            WHERE link_id = $1
+            "#,
+        )
+        .bind(link_id.to_string())
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to find variant views")?;
+
+        Ok(rows)
+    }
+
+    async fn claim_links_due_for_health_check(
+        &self,
+        limit: i64,
+        lease_until: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<Vec<HealthCheckTarget>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let rows = sqlx::query_as::<_, (String, String)>(
+            r#"
+            UPDATE links
+            SET next_health_check_at = $2
+            WHERE id IN (
+                SELECT id FROM links
+                WHERE deleted_at IS NULL AND consumed_at IS NULL AND next_health_check_at <= $3
+                ORDER BY next_health_check_at
+                LIMIT $1
+            )
+            RETURNING id, redirect_url
+            "#,
+        )
+        .bind(limit)
+        .bind(lease_until)
+        .bind(Utc::now())
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to claim links for health check")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, redirect_url)| HealthCheckTarget {
+                link_id: LinkId::from_string(id),
+                url: redirect_url,
+            })
+            .collect())
+    }
+
+    async fn find_link_health_state(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<Option<LinkHealthState>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let row = sqlx::query_as::<_, (i32, Option<chrono::DateTime<chrono::Utc>>)>(
+            "SELECT health_failures, broken_since FROM links WHERE id = $1",
+        )
+        .bind(link_id.to_string())
+        .fetch_optional(&mut **trx)
+        .await
+        .context("failed to find link health state")?;
+
+        Ok(row.map(|(failures, broken_since)| LinkHealthState {
+            failures,
+            broken_since,
+        }))
+    }
+
+    async fn save_link_health_state(
+        &self,
+        link_id: &LinkId,
+        state: &LinkHealthState,
+        next_check_at: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE links
+            SET health_failures = $2, broken_since = $3, next_health_check_at = $4
+            WHERE id = $1
+            "#,
+        )
+        .bind(link_id.to_string())
+        .bind(state.failures)
+        .bind(state.broken_since)
+        .bind(next_check_at)
+        .execute(&mut **trx)
+        .await
+        .context("failed to save link health state")?;
+
+        Ok(())
+    }
+
+    async fn save_health_check(
+        &self,
+        link_id: &LinkId,
+        check: &HealthCheck,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO link_health_checks (link_id, checked_at, outcome, status_code, latency_ms, redirect_chain, error)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(link_id.to_string())
+        .bind(check.checked_at)
+        .bind(check.outcome.as_str())
+        .bind(check.status_code.map(i32::from))
+        .bind(check.latency_ms)
+        .bind(Json(&check.redirect_chain))
+        .bind(&check.error)
+        .execute(&mut **trx)
+        .await
+        .context("failed to save health check")?;
+
+        Ok(())
+    }
+
+    async fn find_health_checks(
+        &self,
+        link_id: &LinkId,
+        limit: i64,
+        ctx: TrxContext,
+    ) -> Result<Vec<HealthCheck>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let checks = sqlx::query_as::<_, HealthCheckRow>(
+            r#"
+            SELECT checked_at, outcome, status_code, latency_ms, redirect_chain, error
+            FROM link_health_checks
+            WHERE link_id = $1
+            ORDER BY checked_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(link_id.to_string())
+        .bind(limit)
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to find health checks")?;
+
+        Ok(checks.into_iter().map(HealthCheck::from).collect())
+    }
+
+    async fn find_link_health_by_user_id(
+        &self,
+        user_id: i32,
+        ctx: TrxContext,
+    ) -> Result<Vec<LinkHealth>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        // no LATERAL in SQLite, the latest check is joined through a subquery
+        let rows = sqlx::query_as::<_, LinkHealthRow>(
+            r#"
+            SELECT l.id, l.redirect_url, l.label, l.health_failures, l.broken_since,
+                c.checked_at, c.outcome, c.status_code, c.latency_ms, c.redirect_chain, c.error
+            FROM links l
+            LEFT JOIN link_health_checks c ON c.id = (
+                SELECT id FROM link_health_checks
+                WHERE link_id = l.id
+                ORDER BY checked_at DESC
+                LIMIT 1
+            )
+            WHERE l.user_id = $1 AND l.deleted_at IS NULL
+            ORDER BY l.broken_since DESC NULLS LAST, l.health_failures DESC, l.created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&mut **trx)
+        .await
+        .context("failed to find link health by user id")?;
+
+        Ok(rows.into_iter().map(LinkHealth::from).collect())
+    }
+
+    async fn delete_health_checks_before(
+        &self,
+        checked_before: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<u64, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let result = sqlx::query("DELETE FROM link_health_checks WHERE checked_at < $1")
+            .bind(checked_before)
+            .execute(&mut **trx)
+            .await
+            .context("failed to delete health checks")?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -1,8 +1,72 @@
-use redis::{AsyncCommands, RedisError, aio::ConnectionManager};
-use serde_json::Error;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use moka::{Expiry, sync::Cache};
 use solar::trx_factory::{TrxContext, TrxFactory, TrxFactoryError};
+use tokio::sync::mpsc;
+
+use crate::{
+    config::{CacheConfig, HealthCheckConfig, LinksConfig},
+    tools::{
+        cache_stats::{CacheStats, TierCounters},
+        id_filter::IdFilter,
+        redis_connection::RedisStatus,
+        url_normalize::normalized_url_hash,
+        user_agent::parse_user_agent,
+    },
+};
+
+use super::entity::{
+    geo_rule::geo_destination,
+    idempotency_key::{IdempotencyKey, IdempotencyRecord},
+    link::{Link, LinkId},
+    link_health::{HealthCheck, HealthCheckTarget, HealthOutcome, LinkHealth, LinkHealthState},
+    link_metadata::LinkMetadata,
+    link_stats::{CountryViews, VariantViews},
+    new_link::{LinkRowError, NewLink, validate_destination},
+    redirect_context::RedirectContext,
+    redirect_type::RedirectType,
+    variant::{Variant, pick_variant, validate_variants},
+};
+
+const MAX_BULK_LINKS: usize = 1000;
+/// A claimed link is handed out again if its check has not been recorded by then.
+const HEALTH_CHECK_LEASE_SEC: i64 = 900;
+const HEALTH_CHECK_HISTORY_LIMIT: i64 = 100;
+const IDEMPOTENCY_PURGE_BATCH_SIZE: i64 = 1000;
+
+/// Destination page of a freshly created link, for the metadata fetcher.
+#[derive(Debug, Clone)]
+pub struct MetadataJob {
+    pub link_id: LinkId,
+    pub url: String,
+}
+
+/// `None` remembers that the link does not exist.
+#[derive(Clone)]
+struct LocalLink {
+    link: Option<Link>,
+    ttl: Duration,
+}
 
-use super::entity::link::{Link, LinkId};
+/// Local entries live no longer than the link may stay cached, see `cache_ttl`.
+struct LocalLinkExpiry;
+
+impl Expiry<String, LocalLink> for LocalLinkExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &String,
+        value: &LocalLink,
+        _created_at: std::time::Instant,
+    ) -> Option<Duration> {
+        Some(value.ttl)
+    }
+}
 
 #[derive(thiserror::Error, Debug)]
 pub enum PersistenceError {
@@ -12,9 +76,49 @@ pub enum PersistenceError {
     InternalError(#[from] eyre::Error),
 }
 
+/// Result of a shared cache lookup.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum CacheLookup {
+    /// `None` remembers that the link does not exist.
+    Hit(Option<Link>),
+    /// Not cached. The version goes to `LinkCache::put` once the database has been read.
+    Miss(u64),
+    /// The cache could not be reached.
+    Unavailable,
+}
+
+/// Link cache shared by every instance. A failing cache only means more database reads,
+/// so nothing here returns an error.
+#[async_trait::async_trait]
+pub trait LinkCache: Send + Sync {
+    async fn get(&self, link_id: &LinkId) -> CacheLookup;
+
+    /// Skipped when the link was invalidated after the lookup that returned `version`.
+    async fn put(&self, link_id: &LinkId, link: Option<&Link>, ttl_sec: u64, version: u64);
+
+    /// Drops the entry everywhere. Runs after the change is committed.
+    async fn invalidate(&self, link_id: &LinkId);
+
+    /// Like `invalidate`, caches that can batch do it in few round trips.
+    async fn invalidate_many(&self, link_ids: &[LinkId]) {
+        for link_id in link_ids {
+            self.invalidate(link_id).await;
+        }
+    }
+
+    fn status(&self) -> RedisStatus;
+
+    /// Commands skipped or failed while the cache was unavailable.
+    fn bypassed(&self) -> u64;
+}
+
 #[async_trait::async_trait]
 pub trait PersistenceRepo: Send + Sync {
+    /// Inserts or updates the link.
     async fn save_link(&self, link: Link, ctx: TrxContext) -> Result<(), PersistenceError>;
+    /// Fails if a link with the id already exists, so a new link never overwrites another.
+    async fn insert_link(&self, link: Link, ctx: TrxContext) -> Result<(), PersistenceError>;
 
     async fn increment_link_views(
         &self,
@@ -22,14 +126,404 @@ pub trait PersistenceRepo: Send + Sync {
         ctx: TrxContext,
     ) -> Result<(), PersistenceError>;
 
+    /// Also uses the page title as label when the link was created without one.
+    async fn save_link_metadata(
+        &self,
+        link_id: &LinkId,
+        metadata: LinkMetadata,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError>;
+
+    /// Marks a one-time link as used. Only the first call for a link returns `true`.
+    async fn consume_link(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<bool, PersistenceError>;
+
+    async fn increment_country_views(
+        &self,
+        link_id: &LinkId,
+        country: &str,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError>;
+
+    async fn find_country_views(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<Vec<CountryViews>, PersistenceError>;
+
+    async fn increment_variant_views(
+        &self,
+        link_id: &LinkId,
+        variant: &str,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError>;
+
+    async fn find_variant_views(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<Vec<(String, i64)>, PersistenceError>;
+
+    /// An id no stored link has yet.
     async fn next_link_id(&self, ctx: TrxContext) -> Result<LinkId, PersistenceError>;
+    /// Ids of every stored link, trashed ones included.
+    async fn find_all_link_ids(&self, ctx: TrxContext) -> Result<Vec<LinkId>, PersistenceError>;
     async fn find_link_by_id(
         &self,
         link_id: &LinkId,
         ctx: TrxContext,
     ) -> Result<Option<Link>, PersistenceError>;
 
+    /// Links of the user that are neither trashed nor used up, oldest first.
+    async fn find_links_by_normalized_url_hash(
+        &self,
+        user_id: i32,
+        normalized_url_hash: &str,
+        ctx: TrxContext,
+    ) -> Result<Vec<Link>, PersistenceError>;
+
+    async fn find_links_by_user_id(
+        &self,
+        user_id: i32,
+        ctx: TrxContext,
+    ) -> Result<Vec<Link>, PersistenceError>;
+
+    /// Moves the link to the trash, it stays restorable until purged.
     async fn delete_link(&self, link_id: LinkId, ctx: TrxContext) -> Result<(), PersistenceError>;
+    async fn restore_link(&self, link_id: &LinkId, ctx: TrxContext)
+    -> Result<(), PersistenceError>;
+    async fn find_deleted_links_by_user_id(
+        &self,
+        user_id: i32,
+        ctx: TrxContext,
+    ) -> Result<Vec<Link>, PersistenceError>;
+    /// Hard deletes links trashed before `deleted_before`, returning their ids.
+    async fn purge_deleted_links(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<Vec<LinkId>, PersistenceError>;
+
+    /// Moves the next check of up to `limit` due links to `lease_until` and returns them, so
+    /// concurrent checkers never pick the same link.
+    async fn claim_links_due_for_health_check(
+        &self,
+        limit: i64,
+        lease_until: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<Vec<HealthCheckTarget>, PersistenceError>;
+    /// Locks the link until the transaction ends.
+    async fn find_link_health_state(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<Option<LinkHealthState>, PersistenceError>;
+    async fn save_link_health_state(
+        &self,
+        link_id: &LinkId,
+        state: &LinkHealthState,
+        next_check_at: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError>;
+    async fn save_health_check(
+        &self,
+        link_id: &LinkId,
+        check: &HealthCheck,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError>;
+    /// Latest checks first.
+    async fn find_health_checks(
+        &self,
+        link_id: &LinkId,
+        limit: i64,
+        ctx: TrxContext,
+    ) -> Result<Vec<HealthCheck>, PersistenceError>;
+    /// Broken links first, each with its latest check.
+    async fn find_link_health_by_user_id(
+        &self,
+        user_id: i32,
+        ctx: TrxContext,
+    ) -> Result<Vec<LinkHealth>, PersistenceError>;
+    async fn delete_health_checks_before(
+        &self,
+        checked_before: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<u64, PersistenceError>;
+
+    /// Returns `false` if a live record with the same key already exists for the user.
+    async fn save_idempotency_record(
+        &self,
+        record: IdempotencyRecord,
+        ctx: TrxContext,
+    ) -> Result<bool, PersistenceError>;
+
+    async fn find_idempotency_record(
+        &self,
+        user_id: i32,
+        key: &str,
+        ctx: TrxContext,
+    ) -> Result<Option<IdempotencyRecord>, PersistenceError>;
+
+    /// Deletes up to `limit` records expired before `expired_before`.
+    async fn delete_expired_idempotency_records(
+        &self,
+        expired_before: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+        ctx: TrxContext,
+    ) -> Result<u64, PersistenceError>;
+
+    /// Called once a change to the link is committed, here or on another instance. Repos
+    /// reading from replicas keep reading it from the primary until they caught up.
+    fn note_link_modified(&self, _link_id: &LinkId) {}
+
+    /// Whether `note_link_modified` must also hear about changes made on other instances.
+    fn reads_from_replicas(&self) -> bool {
+        false
+    }
+}
+
+/// Lets the repo be chosen at runtime, see `DatabaseConfig`.
+#[async_trait::async_trait]
+impl<T: PersistenceRepo + ?Sized> PersistenceRepo for Arc<T> {
+    async fn save_link(&self, link: Link, ctx: TrxContext) -> Result<(), PersistenceError> {
+        (**self).save_link(link, ctx).await
+    }
+
+    async fn insert_link(&self, link: Link, ctx: TrxContext) -> Result<(), PersistenceError> {
+        (**self).insert_link(link, ctx).await
+    }
+
+    async fn increment_link_views(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        (**self).increment_link_views(link_id, ctx).await
+    }
+
+    async fn save_link_metadata(
+        &self,
+        link_id: &LinkId,
+        metadata: LinkMetadata,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        (**self).save_link_metadata(link_id, metadata, ctx).await
+    }
+
+    async fn consume_link(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<bool, PersistenceError> {
+        (**self).consume_link(link_id, ctx).await
+    }
+
+    async fn increment_country_views(
+        &self,
+        link_id: &LinkId,
+        country: &str,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        (**self)
+            .increment_country_views(link_id, country, ctx)
+            .await
+    }
+
+    async fn find_country_views(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<Vec<CountryViews>, PersistenceError> {
+        (**self).find_country_views(link_id, ctx).await
+    }
+
+    async fn increment_variant_views(
+        &self,
+        link_id: &LinkId,
+        variant: &str,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        (**self)
+            .increment_variant_views(link_id, variant, ctx)
+            .await
+    }
+
+    async fn find_variant_views(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<Vec<(String, i64)>, PersistenceError> {
+        (**self).find_variant_views(link_id, ctx).await
+    }
+
+    async fn next_link_id(&self, ctx: TrxContext) -> Result<LinkId, PersistenceError> {
+        (**self).next_link_id(ctx).await
+    }
+
+    async fn find_all_link_ids(&self, ctx: TrxContext) -> Result<Vec<LinkId>, PersistenceError> {
+        (**self).find_all_link_ids(ctx).await
+    }
+
+    async fn find_link_by_id(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<Option<Link>, PersistenceError> {
+        (**self).find_link_by_id(link_id, ctx).await
+    }
+
+    async fn find_links_by_normalized_url_hash(
+        &self,
+        user_id: i32,
+        normalized_url_hash: &str,
+        ctx: TrxContext,
+    ) -> Result<Vec<Link>, PersistenceError> {
+        (**self)
+            .find_links_by_normalized_url_hash(user_id, normalized_url_hash, ctx)
+            .await
+    }
+
+    async fn find_links_by_user_id(
+        &self,
+        user_id: i32,
+        ctx: TrxContext,
+    ) -> Result<Vec<Link>, PersistenceError> {
+        (**self).find_links_by_user_id(user_id, ctx).await
+    }
+
+    async fn delete_link(&self, link_id: LinkId, ctx: TrxContext) -> Result<(), PersistenceError> {
+        (**self).delete_link(link_id, ctx).await
+    }
+
+    async fn restore_link(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        (**self).restore_link(link_id, ctx).await
+    }
+
+    async fn find_deleted_links_by_user_id(
+        &self,
+        user_id: i32,
+        ctx: TrxContext,
+    ) -> Result<Vec<Link>, PersistenceError> {
+        (**self).find_deleted_links_by_user_id(user_id, ctx).await
+    }
+
+    async fn purge_deleted_links(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<Vec<LinkId>, PersistenceError> {
+        (**self).purge_deleted_links(deleted_before, ctx).await
+    }
+
+    async fn claim_links_due_for_health_check(
+        &self,
+        limit: i64,
+        lease_until: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<Vec<HealthCheckTarget>, PersistenceError> {
+        (**self)
+            .claim_links_due_for_health_check(limit, lease_until, ctx)
+            .await
+    }
+
+    async fn find_link_health_state(
+        &self,
+        link_id: &LinkId,
+        ctx: TrxContext,
+    ) -> Result<Option<LinkHealthState>, PersistenceError> {
+        (**self).find_link_health_state(link_id, ctx).await
+    }
+
+    async fn save_link_health_state(
+        &self,
+        link_id: &LinkId,
+        state: &LinkHealthState,
+        next_check_at: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        (**self)
+            .save_link_health_state(link_id, state, next_check_at, ctx)
+            .await
+    }
+
+    async fn save_health_check(
+        &self,
+        link_id: &LinkId,
+        check: &HealthCheck,
+        ctx: TrxContext,
+    ) -> Result<(), PersistenceError> {
+        (**self).save_health_check(link_id, check, ctx).await
+    }
+
+    async fn find_health_checks(
+        &self,
+        link_id: &LinkId,
+        limit: i64,
+        ctx: TrxContext,
+    ) -> Result<Vec<HealthCheck>, PersistenceError> {
+        (**self).find_health_checks(link_id, limit, ctx).await
+    }
+
+    async fn find_link_health_by_user_id(
+        &self,
+        user_id: i32,
+        ctx: TrxContext,
+    ) -> Result<Vec<LinkHealth>, PersistenceError> {
+        (**self).find_link_health_by_user_id(user_id, ctx).await
+    }
+
+    async fn delete_health_checks_before(
+        &self,
+        checked_before: chrono::DateTime<chrono::Utc>,
+        ctx: TrxContext,
+    ) -> Result<u64, PersistenceError> {
+        (**self)
+            .delete_health_checks_before(checked_before, ctx)
+            .await
+    }
+
+    async fn save_idempotency_record(
+        &self,
+        record: IdempotencyRecord,
+        ctx: TrxContext,
+    ) -> Result<bool, PersistenceError> {
+        (**self).save_idempotency_record(record, ctx).await
+    }
+
+    async fn find_idempotency_record(
+        &self,
+        user_id: i32,
+        key: &str,
+        ctx: TrxContext,
+    ) -> Result<Option<IdempotencyRecord>, PersistenceError> {
+        (**self).find_idempotency_record(user_id, key, ctx).await
+    }
+
+    async fn delete_expired_idempotency_records(
+        &self,
+        expired_before: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+        ctx: TrxContext,
+    ) -> Result<u64, PersistenceError> {
+        (**self)
+            .delete_expired_idempotency_records(expired_before, limit, ctx)
+            .await
+    }
+
+    fn note_link_modified(&self, link_id: &LinkId) {
+        (**self).note_link_modified(link_id)
+    }
+
+    fn reads_from_replicas(&self) -> bool {
+        (**self).reads_from_replicas()
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -43,15 +537,40 @@ pub enum LinkManagerError {
     LinkNotFound(LinkId),
     #[error("link not owned by user: {0}, {1}")]
     LinkNotOwnedByUser(LinkId, i32),
-
-    #[error("failed to deserialize: {0}")]
-    CacheError(Error),
+    #[error("invalid rows: {0:?}")]
+    InvalidRows(Vec<LinkRowError>),
+    #[error("too many links, max: {0}")]
+    TooManyLinks(usize),
+    #[error("idempotency key reused with a different request: {0}")]
+    IdempotencyKeyReused(String),
+    #[error("unknown utm template: {0}")]
+    UnknownUtmTemplate(String),
+    #[error("invalid link options: {0}")]
+    InvalidOptions(String),
+    #[error("link can not be previewed: {0}")]
+    PreviewNotAllowed(LinkId),
+    #[error("one-time link already used: {0}")]
+    LinkConsumed(LinkId),
+    #[error("link not active until: {0}")]
+    NotYetActive(chrono::DateTime<chrono::Utc>),
 }
 pub struct LinkManagerService<P, T> {
     persistence_repo: P,
     trx_factory: T,
-    redis_client: ConnectionManager,
+    link_cache: Arc<dyn LinkCache>,
     cache_expr_sec: u64,
+    idempotency_expr_sec: u64,
+    config: LinksConfig,
+    metadata_jobs: Option<mpsc::Sender<MetadataJob>>,
+    local_cache: Option<Cache<String, LocalLink>>,
+    local_ttl: Duration,
+    /// Bumped on every local eviction, see `cache_locally`.
+    local_generation: AtomicU64,
+    negative_ttl_sec: u64,
+    link_id_filter: Option<IdFilter>,
+    local_stats: TierCounters,
+    redis_stats: TierCounters,
+    bloom_rejections: AtomicU64,
 }
 
 impl<P, T> LinkManagerService<P, T>
@@ -62,54 +581,551 @@ where
     pub fn new(
         persistence_repo: P,
         trx_factory: T,
-        redis_client: ConnectionManager,
+        link_cache: Arc<dyn LinkCache>,
         cache_expr_sec: u64,
+        idempotency_expr_sec: u64,
+        config: LinksConfig,
     ) -> Self {
         Self {
             persistence_repo,
             trx_factory,
-            redis_client,
+            link_cache,
             cache_expr_sec,
+            idempotency_expr_sec,
+            config,
+            metadata_jobs: None,
+            local_cache: None,
+            local_ttl: Duration::ZERO,
+            local_generation: AtomicU64::new(0),
+            negative_ttl_sec: 0,
+            link_id_filter: None,
+            local_stats: TierCounters::default(),
+            redis_stats: TierCounters::default(),
+            bloom_rejections: AtomicU64::new(0),
         }
     }
 
-    pub async fn create_link(
-        &self,
-        user_id: i32,
-        redirect_url: String,
-        label: String,
-    ) -> Result<LinkId, LinkManagerError> {
-        let link: Link = self
-            .trx_factory
-            .begin(async move |ctx| -> Result<Link, LinkManagerError> {
-                let link_id = self.persistence_repo.next_link_id(ctx.clone()).await?;
-                let link = Link::new(link_id, user_id, redirect_url, label);
-                self.persistence_repo
-                    .save_link(link.clone(), ctx.clone())
-                    .await?;
-
-                Ok(link)
-            })
-            .await?;
+    /// Keeps recently viewed links in process in front of Redis, remembers unknown link ids
+    /// and optionally keeps a Bloom filter of known ones. Every instance applies invalidations
+    /// published by the link cache through `on_invalidation_message`.
+    pub fn with_cache_config(mut self, cache_config: &CacheConfig) -> Self {
+        if cache_config.local_capacity > 0 {
+            self.local_cache = Some(
+                Cache::builder()
+                    .max_capacity(cache_config.local_capacity)
+                    .expire_after(LocalLinkExpiry)
+                    .build(),
+            );
+        }
+        self.local_ttl = Duration::from_millis(cache_config.local_ttl_ms);
+        self.negative_ttl_sec = cache_config.negative_ttl_sec;
+        if cache_config.bloom_filter {
+            self.link_id_filter = Some(IdFilter::new(cache_config.bloom_false_positive_rate));
+        }
+        self
+    }
 
-        Ok(link.id.clone())
+    /// New links are queued here so their destination metadata is fetched in the background.
+    pub fn with_metadata_jobs(mut self, metadata_jobs: mpsc::Sender<MetadataJob>) -> Self {
+        self.metadata_jobs = Some(metadata_jobs);
+        self
     }
 
-    pub async fn view_link(&self, link_id: &LinkId) -> Result<Link, LinkManagerError> {
-        let link = self
-            .trx_factory
-            .begin(async move |ctx| -> Result<Link, LinkManagerError> {
-                let existing_link = self.get_and_cache_link(link_id, ctx.clone()).await?;
+    fn enqueue_metadata_job(&self, link_id: LinkId, url: String) {
+        let Some(metadata_jobs) = &self.metadata_jobs else {
+            return;
+        };
+
+        // a full queue only means the link goes without metadata
+        if let Err(e) = metadata_jobs.try_send(MetadataJob { link_id, url }) {
+            println!("failed to queue metadata fetch: {e}");
+        }
+    }
 
+    pub async fn save_link_metadata(
+        &self,
+        link_id: &LinkId,
+        metadata: LinkMetadata,
+    ) -> Result<(), LinkManagerError> {
+        self.trx_factory
+            .begin(async move |ctx| -> Result<(), LinkManagerError> {
                 self.persistence_repo
-                    .increment_link_views(link_id, ctx.clone())
+                    .save_link_metadata(link_id, metadata, ctx.clone())
                     .await?;
-
-                Ok(existing_link)
+                Ok(())
             })
             .await?;
 
-        Ok(link)
+        // social cards are rendered from the cached link
+        self.invalidate_cached_link(link_id).await;
+
+        Ok(())
+    }
+
+    fn validate_options(&self, new_link: &NewLink) -> Result<(), LinkManagerError> {
+        if let Some(template) = &new_link.options.utm_template
+            && !self.config.utm_templates.contains_key(template)
+        {
+            return Err(LinkManagerError::UnknownUtmTemplate(template.clone()));
+        }
+
+        validate_variants(&new_link.options.variants).map_err(LinkManagerError::InvalidOptions)?;
+        new_link
+            .options
+            .schedule
+            .validate()
+            .map_err(LinkManagerError::InvalidOptions)?;
+        new_link
+            .options
+            .languages
+            .validate()
+            .map_err(LinkManagerError::InvalidOptions)?;
+        new_link
+            .options
+            .og
+            .validate()
+            .map_err(LinkManagerError::InvalidOptions)?;
+
+        let geo_destinations = new_link.options.geo.iter().map(|r| r.destination.as_str());
+        let variant_destinations = new_link
+            .options
+            .variants
+            .iter()
+            .map(|v| v.destination.as_str());
+        for destination in new_link
+            .options
+            .routing
+            .destinations()
+            .chain(geo_destinations)
+            .chain(new_link.options.languages.destinations())
+            .chain(new_link.options.schedule.destinations())
+            .chain(variant_destinations)
+            .chain(new_link.options.fallback_url.as_deref())
+        {
+            validate_destination(destination).map_err(LinkManagerError::InvalidOptions)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn redirect_type(&self, link: &Link) -> RedirectType {
+        link.options
+            .redirect_type
+            .unwrap_or(self.config.default_redirect_type)
+    }
+
+    /// Whether the hit should get the link's social card instead of a redirect.
+    pub fn wants_social_card(&self, link: &Link, redirect_ctx: &RedirectContext) -> bool {
+        !link.options.og.is_empty() && self.is_crawler(redirect_ctx)
+    }
+
+    /// Whether the hit comes from a link preview crawler (configurable User-Agent list).
+    pub fn is_crawler(&self, redirect_ctx: &RedirectContext) -> bool {
+        let Some(user_agent) = &redirect_ctx.user_agent else {
+            return false;
+        };
+
+        let user_agent = user_agent.to_lowercase();
+        self.config
+            .crawler_user_agents
+            .iter()
+            .any(|crawler| user_agent.contains(&crawler.to_lowercase()))
+    }
+
+    /// First match of User-Agent routing, geo, the visitor's language, then time windows.
+    /// The language default comes last, so it does not shadow the schedule. `None` leaves
+    /// the choice to the A/B variants and then `redirect_url` or its fallback.
+    fn rule_destination<'a>(
+        &self,
+        link: &'a Link,
+        redirect_ctx: &RedirectContext,
+    ) -> Option<&'a str> {
+        if !link.options.routing.is_empty()
+            && let Some(user_agent) = &redirect_ctx.user_agent
+            && let Some(routed) = link
+                .options
+                .routing
+                .destination_for(&parse_user_agent(user_agent))
+        {
+            return Some(routed);
+        }
+
+        redirect_ctx
+            .geo
+            .as_ref()
+            .and_then(|geo| geo_destination(&link.options.geo, geo))
+            .or_else(|| {
+                link.options
+                    .languages
+                    .destination_for(redirect_ctx.accept_language.as_deref())
+            })
+            .or_else(|| link.options.schedule.destination_at(redirect_ctx.at))
+            .or(link.options.languages.default.as_deref())
+    }
+
+    /// Scheduled and one-time links must not be cached by browsers. Neither may links sending
+    /// visitors to different places, a shared cache would pin one visitor's destination.
+    pub fn cache_control(&self, link: &Link) -> String {
+        if varies_per_visitor(link) {
+            "private, no-store".to_string()
+        } else if link.options.schedule.is_empty() && !link.options.one_time {
+            self.redirect_type(link).cache_control()
+        } else {
+            "no-store".to_string()
+        }
+    }
+
+    /// Cache TTL for the link, cut short at the next activation or time-window boundary.
+    fn cache_ttl(&self, link: &Link) -> u64 {
+        let now = chrono::Utc::now();
+        let boundary = link
+            .active_from
+            .filter(|active_from| *active_from > now)
+            .into_iter()
+            .chain(link.options.schedule.next_boundary(now))
+            .min();
+
+        match boundary {
+            Some(boundary) => {
+                ((boundary - now).num_seconds().max(1) as u64).min(self.cache_expr_sec)
+            }
+            None => self.cache_expr_sec,
+        }
+    }
+
+    /// A/B variant the visitor is sent to, if the link has variants and no routing, geo,
+    /// language or schedule rule takes precedence. The sticky cookie variant is kept; new visitors are assigned
+    /// by a hash of their IP, so the choice is stable across requests.
+    pub fn choose_variant<'a>(
+        &self,
+        link: &'a Link,
+        redirect_ctx: &RedirectContext,
+    ) -> Option<&'a Variant> {
+        if link.options.variants.is_empty() || self.rule_destination(link, redirect_ctx).is_some() {
+            return None;
+        }
+
+        let mut seed = link.id.to_string().into_bytes();
+        if let Some(client_ip) = redirect_ctx.client_ip {
+            seed.extend_from_slice(client_ip.to_string().as_bytes());
+        }
+
+        pick_variant(
+            &link.options.variants,
+            redirect_ctx.variant.as_deref(),
+            &seed,
+        )
+    }
+
+    /// Final redirect target: the rule destination (see `rule_destination`), or else the
+    /// chosen A/B variant replaces `redirect_url` (or its fallback while broken), then
+    /// forwarded path and query are applied, then UTM params. Link UTM params win over its
+    /// campaign template, which wins over the configured defaults.
+    pub fn resolve_destination(&self, link: &Link, redirect_ctx: &RedirectContext) -> String {
+        let mut destination = self
+            .rule_destination(link, redirect_ctx)
+            .or_else(|| {
+                self.choose_variant(link, redirect_ctx)
+                    .map(|v| v.destination.as_str())
+            })
+            .unwrap_or_else(|| link.primary_destination())
+            .to_string();
+
+        if link.options.forward_path {
+            destination = redirect_ctx.apply_path(&destination);
+        }
+        if link.options.forward_query {
+            destination = redirect_ctx.apply_query(&destination);
+        }
+
+        let mut utm = link.options.utm.clone();
+        if let Some(template) = link
+            .options
+            .utm_template
+            .as_ref()
+            .and_then(|t| self.config.utm_templates.get(t))
+        {
+            utm = utm.or(template);
+        }
+        utm = utm.or(&self.config.utm_defaults);
+
+        utm.apply_to(&destination)
+    }
+
+    /// With an idempotency key, a retry of the same request replays the original link id
+    /// instead of creating a duplicate link. With `reuse_existing`, a link of the user with
+    /// the same normalized destination that behaves as requested is returned instead of
+    /// creating a new one, see `is_reusable_for`.
+    pub async fn create_link(
+        &self,
+        user_id: i32,
+        new_link: NewLink,
+        reuse_existing: bool,
+        idempotency_key: Option<IdempotencyKey>,
+    ) -> Result<LinkId, LinkManagerError> {
+        new_link
+            .validate()
+            .map_err(LinkManagerError::InvalidOptions)?;
+        self.validate_options(&new_link)?;
+        let url_hash = normalized_url_hash(&new_link.redirect_url, &self.config.tracking_params);
+
+        let (link_id, created_link) = self
+            .trx_factory
+            .begin(
+                async move |ctx| -> Result<(LinkId, Option<Link>), LinkManagerError> {
+                    let existing_link = match (&url_hash, reuse_existing) {
+                        (Some(url_hash), true) => {
+                            self.find_reusable_link(user_id, &new_link, url_hash, ctx.clone())
+                                .await?
+                        }
+                        _ => None,
+                    };
+
+                    let link_id = match &existing_link {
+                        Some(link) => link.id.clone(),
+                        None => self.persistence_repo.next_link_id(ctx.clone()).await?,
+                    };
+
+                    if let Some(idempotency_key) = idempotency_key {
+                        let record = IdempotencyRecord::new(
+                            user_id,
+                            idempotency_key,
+                            link_id.clone(),
+                            self.idempotency_expr_sec,
+                        );
+                        let saved = self
+                            .persistence_repo
+                            .save_idempotency_record(record.clone(), ctx.clone())
+                            .await?;
+
+                        if !saved {
+                            let existing = self
+                                .persistence_repo
+                                .find_idempotency_record(user_id, &record.key, ctx.clone())
+                                .await?
+                                .ok_or(PersistenceError::InternalError(eyre::eyre!(
+                                    "idempotency record disappeared"
+                                )))?;
+
+                            if existing.fingerprint != record.fingerprint {
+                                return Err(LinkManagerError::IdempotencyKeyReused(
+                                    record.key.clone(),
+                                ));
+                            }
+
+                            return Ok((existing.link_id.clone(), None));
+                        }
+                    }
+
+                    if existing_link.is_some() {
+                        return Ok((link_id, None));
+                    }
+
+                    let link = Link::new(
+                        link_id,
+                        user_id,
+                        new_link.redirect_url,
+                        new_link.label,
+                        url_hash,
+                        new_link.active_from,
+                        new_link.options,
+                    );
+                    self.persistence_repo
+                        .insert_link(link.clone(), ctx.clone())
+                        .await?;
+
+                    Ok((link.id.clone(), Some(link)))
+                },
+            )
+            .await?;
+
+        if let Some(link) = created_link {
+            self.cache_created_link(&link).await;
+            self.enqueue_metadata_job(link.id.clone(), link.redirect_url.clone());
+        }
+
+        Ok(link_id)
+    }
+
+    async fn find_reusable_link(
+        &self,
+        user_id: i32,
+        new_link: &NewLink,
+        url_hash: &str,
+        ctx: TrxContext,
+    ) -> Result<Option<Link>, LinkManagerError> {
+        let links = self
+            .persistence_repo
+            .find_links_by_normalized_url_hash(user_id, url_hash, ctx)
+            .await?;
+
+        Ok(links
+            .into_iter()
+            .find(|link| is_reusable_for(link, new_link)))
+    }
+
+    /// Each link comes with its `reuse_existing` flag, which works as in `create_link`.
+    pub async fn create_links(
+        &self,
+        user_id: i32,
+        new_links: Vec<(NewLink, bool)>,
+    ) -> Result<Vec<LinkId>, LinkManagerError> {
+        if new_links.len() > MAX_BULK_LINKS {
+            return Err(LinkManagerError::TooManyLinks(MAX_BULK_LINKS));
+        }
+
+        let row_errors: Vec<LinkRowError> = new_links
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (new_link, _))| {
+                new_link
+                    .validate()
+                    .and_then(|_| self.validate_options(new_link).map_err(|e| e.to_string()))
+                    .err()
+                    .map(|message| LinkRowError::new(i + 1, message))
+            })
+            .collect();
+        if !row_errors.is_empty() {
+            return Err(LinkManagerError::InvalidRows(row_errors));
+        }
+
+        let (link_ids, links) = self
+            .trx_factory
+            .begin(
+                async move |ctx| -> Result<(Vec<LinkId>, Vec<Link>), LinkManagerError> {
+                    let mut link_ids = Vec::with_capacity(new_links.len());
+                    let mut links = Vec::with_capacity(new_links.len());
+                    for (new_link, reuse_existing) in new_links {
+                        let url_hash = normalized_url_hash(
+                            &new_link.redirect_url,
+                            &self.config.tracking_params,
+                        );
+                        if let (Some(url_hash), true) = (&url_hash, reuse_existing)
+                            && let Some(existing_link) = self
+                                .find_reusable_link(user_id, &new_link, url_hash, ctx.clone())
+                                .await?
+                        {
+                            link_ids.push(existing_link.id.clone());
+                            continue;
+                        }
+
+                        let link_id = self.persistence_repo.next_link_id(ctx.clone()).await?;
+                        let link = Link::new(
+                            link_id,
+                            user_id,
+                            new_link.redirect_url,
+                            new_link.label,
+                            url_hash,
+                            new_link.active_from,
+                            new_link.options,
+                        );
+                        self.persistence_repo
+                            .insert_link(link.clone(), ctx.clone())
+                            .await?;
+
+                        link_ids.push(link.id.clone());
+                        links.push(link);
+                    }
+
+                    Ok((link_ids, links))
+                },
+            )
+            .await?;
+
+        let created_link_ids: Vec<LinkId> = links.iter().map(|link| link.id.clone()).collect();
+        self.cache_created_links(&created_link_ids).await;
+        for link in &links {
+            self.enqueue_metadata_job(link.id.clone(), link.redirect_url.clone());
+        }
+
+        Ok(link_ids)
+    }
+
+    /// The link is looked up outside the transaction, so it can come from the cache or a
+    /// read replica. Only the one-time consume and the view counters are written in one.
+    pub async fn view_link(
+        &self,
+        link_id: &LinkId,
+        redirect_ctx: &RedirectContext,
+    ) -> Result<Link, LinkManagerError> {
+        let link = self.get_and_cache_link(link_id, TrxContext::Empty).await?;
+        check_visitable(&link, redirect_ctx)?;
+
+        let one_time = link.options.one_time;
+        let variant = self.choose_variant(&link, redirect_ctx);
+        self.trx_factory
+            .begin(async move |ctx| -> Result<(), LinkManagerError> {
+                // the consume is atomic, a stale lookup can not let a second visitor through
+                if one_time
+                    && !self
+                        .persistence_repo
+                        .consume_link(link_id, ctx.clone())
+                        .await?
+                {
+                    return Err(LinkManagerError::LinkConsumed(link_id.clone()));
+                }
+
+                self.persistence_repo
+                    .increment_link_views(link_id, ctx.clone())
+                    .await?;
+
+                if let Some(geo) = &redirect_ctx.geo {
+                    self.persistence_repo
+                        .increment_country_views(link_id, &geo.country, ctx.clone())
+                        .await?;
+                }
+
+                if let Some(variant) = variant {
+                    self.persistence_repo
+                        .increment_variant_views(link_id, &variant.name, ctx.clone())
+                        .await?;
+                }
+
+                Ok(())
+            })
+            .await?;
+
+        if one_time {
+            self.invalidate_cached_link(link_id).await;
+        }
+
+        Ok(link)
+    }
+
+    /// A crawler hit, looked up without counting a view, so chat unfurls neither use up
+    /// one-time links nor show in the stats. One-time links are only unfurled through their
+    /// social card, which leaves out the destination.
+    pub async fn crawler_view(
+        &self,
+        link_id: &LinkId,
+        redirect_ctx: &RedirectContext,
+    ) -> Result<Link, LinkManagerError> {
+        let link = self.get_and_cache_link(link_id, TrxContext::Empty).await?;
+        check_visitable(&link, redirect_ctx)?;
+        if link.options.one_time && link.options.og.is_empty() {
+            return Err(LinkManagerError::PreviewNotAllowed(link_id.clone()));
+        }
+
+        Ok(link)
+    }
+
+    /// The link as a visitor would reach it, without counting a view. One-time links are
+    /// never revealed.
+    pub async fn preview_link(&self, link_id: &LinkId) -> Result<Link, LinkManagerError> {
+        let link = self.get_and_cache_link(link_id, TrxContext::Empty).await?;
+        if link.deleted_at.is_some() {
+            return Err(LinkManagerError::LinkNotFound(link_id.clone()));
+        }
+        if let Some(active_from) = link.active_from
+            && !link.is_active_at(chrono::Utc::now())
+        {
+            return Err(LinkManagerError::NotYetActive(active_from));
+        }
+        if link.options.one_time {
+            return Err(LinkManagerError::PreviewNotAllowed(link_id.clone()));
+        }
+
+        Ok(link)
     }
 
     async fn get_and_cache_link(
@@ -117,31 +1133,183 @@ where
         link_id: &LinkId,
         ctx: TrxContext,
     ) -> Result<Link, LinkManagerError> {
-        let mut r = self.redis_client.clone();
-        let string_link: Result<String, RedisError> = r.get(link_id.to_string()).await;
+        if let Some(link_id_filter) = &self.link_id_filter
+            && !link_id_filter.might_contain(&link_id.value)
+        {
+            self.bloom_rejections.fetch_add(1, Ordering::Relaxed);
+            return Err(LinkManagerError::LinkNotFound(link_id.clone()));
+        }
 
-        if let Ok(s) = string_link {
-            let link = serde_json::from_str(&s).map_err(|e| LinkManagerError::CacheError(e))?;
+        let generation = self.local_generation.load(Ordering::Acquire);
+        if let Some(local_cache) = &self.local_cache {
+            if let Some(cached) = local_cache.get(&link_id.value) {
+                self.local_stats.hit();
+                return cached
+                    .link
+                    .ok_or(LinkManagerError::LinkNotFound(link_id.clone()));
+            }
+            self.local_stats.miss();
+        }
 
-            return Ok(link);
-        } else {
-            let link = self
-                .persistence_repo
-                .find_link_by_id(link_id, ctx.clone())
-                .await?
-                .ok_or(LinkManagerError::LinkNotFound(link_id.clone()))?;
-
-            if let Ok(serialized) = serde_json::to_string(&link) {
-                let mut r_clone = self.redis_client.clone();
-                let key = link_id.to_string();
-                let expire = self.cache_expr_sec;
-
-                tokio::spawn(async move {
-                    let _: Result<(), RedisError> = r_clone.set_ex(key, serialized, expire).await;
-                });
+        let version = match self.link_cache.get(link_id).await {
+            CacheLookup::Hit(link) => {
+                self.redis_stats.hit();
+                self.cache_locally(link_id, link.as_ref(), generation);
+                return link.ok_or(LinkManagerError::LinkNotFound(link_id.clone()));
+            }
+            CacheLookup::Miss(version) => Some(version),
+            // a failing cache counts as a miss, the link is then read from the database
+            CacheLookup::Unavailable => None,
+        };
+        self.redis_stats.miss();
+
+        let link = self
+            .persistence_repo
+            .find_link_by_id(link_id, ctx.clone())
+            .await?;
+        let ttl_sec = match &link {
+            Some(link) => self.cache_ttl(link),
+            None => self.negative_ttl_sec,
+        };
+        if let Some(version) = version
+            && ttl_sec > 0
+        {
+            let link_cache = self.link_cache.clone();
+            let link_id = link_id.clone();
+            let link = link.clone();
+            tokio::spawn(async move {
+                link_cache
+                    .put(&link_id, link.as_ref(), ttl_sec, version)
+                    .await;
+            });
+        }
+        self.cache_locally(link_id, link.as_ref(), generation);
+
+        link.ok_or(LinkManagerError::LinkNotFound(link_id.clone()))
+    }
+
+    /// Skipped if anything was evicted since the lookup started at `generation`, the
+    /// eviction may have come from a change the lookup did not see.
+    fn cache_locally(&self, link_id: &LinkId, link: Option<&Link>, generation: u64) {
+        let Some(local_cache) = &self.local_cache else {
+            return;
+        };
+        let ttl_sec = match link {
+            Some(link) => self.cache_ttl(link),
+            None => self.negative_ttl_sec,
+        };
+        if ttl_sec == 0 || self.local_generation.load(Ordering::Acquire) != generation {
+            return;
+        }
+
+        let ttl = self.local_ttl.min(Duration::from_secs(ttl_sec));
+        local_cache.insert(
+            link_id.value.clone(),
+            LocalLink {
+                link: link.cloned(),
+                ttl,
+            },
+        );
+        // an eviction between the check and the insert must still win
+        if self.local_generation.load(Ordering::Acquire) != generation {
+            local_cache.invalidate(&link_id.value);
+        }
+    }
+
+    /// Drops a remembered miss for the new link everywhere.
+    async fn cache_created_link(&self, link: &Link) {
+        self.cache_created_links(std::slice::from_ref(&link.id))
+            .await;
+    }
+
+    /// Invalidates in batches, a bulk create does not wait a round trip per link.
+    async fn cache_created_links(&self, link_ids: &[LinkId]) {
+        for link_id in link_ids {
+            if let Some(link_id_filter) = &self.link_id_filter {
+                link_id_filter.insert(&link_id.value);
             }
+            self.persistence_repo.note_link_modified(link_id);
+            self.evict_local_link(link_id);
+        }
+        self.link_cache.invalidate_many(link_ids).await;
+    }
+
+    fn evict_local_link(&self, link_id: &LinkId) {
+        self.local_generation.fetch_add(1, Ordering::AcqRel);
+        if let Some(local_cache) = &self.local_cache {
+            local_cache.invalidate(&link_id.value);
+        }
+    }
 
-            Ok(link)
+    /// Applies an invalidation published by any instance. The link may have just been
+    /// created, so it also goes into the Bloom filter.
+    pub fn on_invalidation_message(&self, link_id: &LinkId) {
+        self.persistence_repo.note_link_modified(link_id);
+        if let Some(link_id_filter) = &self.link_id_filter {
+            link_id_filter.insert(&link_id.value);
+        }
+        self.evict_local_link(link_id);
+    }
+
+    /// Drops the local tier and the Bloom filter until its next rebuild, for when
+    /// invalidation messages may have been missed.
+    pub fn on_invalidations_lost(&self) {
+        self.local_generation.fetch_add(1, Ordering::AcqRel);
+        if let Some(local_cache) = &self.local_cache {
+            local_cache.invalidate_all();
+        }
+        if let Some(link_id_filter) = &self.link_id_filter {
+            link_id_filter.reset();
+        }
+    }
+
+    /// Whether this instance keeps state that other instances' invalidations must reach.
+    pub fn listens_for_invalidations(&self) -> bool {
+        self.local_cache.is_some()
+            || self.link_id_filter.is_some()
+            || self.persistence_repo.reads_from_replicas()
+    }
+
+    pub fn has_link_id_filter(&self) -> bool {
+        self.link_id_filter.is_some()
+    }
+
+    /// Reloads the Bloom filter from every stored link id, returning how many there are.
+    pub async fn rebuild_link_id_filter(&self) -> Result<usize, LinkManagerError> {
+        let Some(link_id_filter) = &self.link_id_filter else {
+            return Ok(0);
+        };
+
+        link_id_filter.start_rebuild();
+        let link_ids = match self
+            .persistence_repo
+            .find_all_link_ids(TrxContext::Empty)
+            .await
+        {
+            Ok(link_ids) => link_ids,
+            Err(e) => {
+                link_id_filter.cancel_rebuild();
+                return Err(e.into());
+            }
+        };
+
+        let count = link_ids.len();
+        link_id_filter.finish_rebuild(link_ids.iter().map(|link_id| link_id.to_string()));
+
+        Ok(count)
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats {
+            local: self.local_stats.snapshot(),
+            local_entries: self
+                .local_cache
+                .as_ref()
+                .map_or(0, |local_cache| local_cache.entry_count()),
+            redis: self.redis_stats.snapshot(),
+            bloom_rejections: self.bloom_rejections.load(Ordering::Relaxed),
+            redis_status: self.link_cache.status(),
+            redis_bypassed: self.link_cache.bypassed(),
         }
     }
 
@@ -155,13 +1323,86 @@ where
         Ok(link.views)
     }
 
+    pub async fn get_link_country_views(
+        &self,
+        link_id: &LinkId,
+    ) -> Result<Vec<CountryViews>, LinkManagerError> {
+        self.persistence_repo
+            .find_link_by_id(link_id, TrxContext::Empty)
+            .await?
+            .ok_or(LinkManagerError::LinkNotFound(link_id.clone()))?;
+
+        let views = self
+            .persistence_repo
+            .find_country_views(link_id, TrxContext::Empty)
+            .await?;
+
+        Ok(views)
+    }
+
+    /// Views per variant of the link, variants without views included.
+    pub async fn get_link_variant_views(
+        &self,
+        link_id: &LinkId,
+    ) -> Result<Vec<VariantViews>, LinkManagerError> {
+        let link = self
+            .persistence_repo
+            .find_link_by_id(link_id, TrxContext::Empty)
+            .await?
+            .ok_or(LinkManagerError::LinkNotFound(link_id.clone()))?;
+
+        let views = self
+            .persistence_repo
+            .find_variant_views(link_id, TrxContext::Empty)
+            .await?;
+
+        let variant_views = link
+            .options
+            .variants
+            .iter()
+            .map(|variant| VariantViews {
+                variant: variant.name.clone(),
+                destination: variant.destination.clone(),
+                weight: variant.weight,
+                views: views
+                    .iter()
+                    .find(|(name, _)| *name == variant.name)
+                    .map(|(_, views)| *views)
+                    .unwrap_or(0),
+            })
+            .collect();
+
+        Ok(variant_views)
+    }
+
+    pub async fn get_user_links(&self, user_id: i32) -> Result<Vec<Link>, LinkManagerError> {
+        let links = self
+            .persistence_repo
+            .find_links_by_user_id(user_id, TrxContext::Empty)
+            .await?;
+
+        Ok(links)
+    }
+
+    /// Called after commit, see `LinkCache::put` for lookups racing with the change.
+    async fn invalidate_cached_link(&self, link_id: &LinkId) {
+        // before the eviction, so the next miss does not refill it from a lagging replica
+        self.persistence_repo.note_link_modified(link_id);
+        self.evict_local_link(link_id);
+        self.link_cache.invalidate(link_id).await;
+    }
+
+    /// Moves the link to the trash. The cache entry is dropped after commit, so the link
+    /// stops redirecting right away.
     pub async fn delete_link(&self, link_id: LinkId, user_id: i32) -> Result<(), LinkManagerError> {
+        let deleted_link_id = link_id.clone();
         self.trx_factory
             .begin(async move |ctx| -> Result<(), LinkManagerError> {
                 let link = self
                     .persistence_repo
                     .find_link_by_id(&link_id, TrxContext::Empty)
                     .await?
+                    .filter(|link| link.deleted_at.is_none())
                     .ok_or(LinkManagerError::LinkNotFound(link_id.clone()))?;
 
                 if link.user_id != user_id {
@@ -178,6 +1419,805 @@ where
             })
             .await?;
 
+        self.invalidate_cached_link(&deleted_link_id).await;
+
         Ok(())
     }
+
+    pub async fn restore_link(
+        &self,
+        link_id: &LinkId,
+        user_id: i32,
+    ) -> Result<(), LinkManagerError> {
+        self.trx_factory
+            .begin(async move |ctx| -> Result<(), LinkManagerError> {
+                let link = self
+                    .persistence_repo
+                    .find_link_by_id(link_id, ctx.clone())
+                    .await?
+                    .filter(|link| link.deleted_at.is_some())
+                    .ok_or(LinkManagerError::LinkNotFound(link_id.clone()))?;
+
+                if link.user_id != user_id {
+                    return Err(LinkManagerError::LinkNotOwnedByUser(
+                        link_id.clone(),
+                        user_id,
+                    ));
+                }
+
+                self.persistence_repo
+                    .restore_link(link_id, ctx.clone())
+                    .await?;
+                Ok(())
+            })
+            .await?;
+
+        self.invalidate_cached_link(link_id).await;
+
+        Ok(())
+    }
+
+    pub async fn get_trashed_links(&self, user_id: i32) -> Result<Vec<Link>, LinkManagerError> {
+        let links = self
+            .persistence_repo
+            .find_deleted_links_by_user_id(user_id, TrxContext::Empty)
+            .await?;
+
+        Ok(links)
+    }
+
+    /// Hard deletes links that have been in the trash for longer than `retention`.
+    pub async fn purge_trash(
+        &self,
+        retention: chrono::Duration,
+    ) -> Result<usize, LinkManagerError> {
+        let deleted_before = chrono::Utc::now() - retention;
+        let purged = self
+            .trx_factory
+            .begin(async move |ctx| -> Result<Vec<LinkId>, LinkManagerError> {
+                let purged = self
+                    .persistence_repo
+                    .purge_deleted_links(deleted_before, ctx.clone())
+                    .await?;
+                Ok(purged)
+            })
+            .await?;
+
+        for link_id in &purged {
+            self.invalidate_cached_link(link_id).await;
+        }
+
+        Ok(purged.len())
+    }
+
+    /// Deletes expired idempotency records in batches, so no transaction holds many rows.
+    pub async fn purge_expired_idempotency_records(&self) -> Result<u64, LinkManagerError> {
+        let expired_before = chrono::Utc::now();
+        let mut purged = 0;
+        loop {
+            let deleted = self
+                .trx_factory
+                .begin(async move |ctx| -> Result<u64, LinkManagerError> {
+                    let deleted = self
+                        .persistence_repo
+                        .delete_expired_idempotency_records(
+                            expired_before,
+                            IDEMPOTENCY_PURGE_BATCH_SIZE,
+                            ctx.clone(),
+                        )
+                        .await?;
+                    Ok(deleted)
+                })
+                .await?;
+            purged += deleted;
+            if deleted < IDEMPOTENCY_PURGE_BATCH_SIZE as u64 {
+                return Ok(purged);
+            }
+        }
+    }
+
+    /// Links whose destination is due for a check, the result goes to `record_health_check`.
+    pub async fn claim_health_check_targets(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<HealthCheckTarget>, LinkManagerError> {
+        let lease_until = chrono::Utc::now() + chrono::Duration::seconds(HEALTH_CHECK_LEASE_SEC);
+        let targets = self
+            .trx_factory
+            .begin(
+                async move |ctx| -> Result<Vec<HealthCheckTarget>, LinkManagerError> {
+                    let targets = self
+                        .persistence_repo
+                        .claim_links_due_for_health_check(limit, lease_until, ctx.clone())
+                        .await?;
+                    Ok(targets)
+                },
+            )
+            .await?;
+
+        Ok(targets)
+    }
+
+    /// Stores the check and updates the link's health. Failed checks are retried with
+    /// exponential backoff. When the link turns broken or recovers, its cache entry is
+    /// dropped so redirects switch to or from the fallback url right away.
+    pub async fn record_health_check(
+        &self,
+        link_id: &LinkId,
+        check: HealthCheck,
+        config: &HealthCheckConfig,
+    ) -> Result<(), LinkManagerError> {
+        let (was_broken, is_broken) = self
+            .trx_factory
+            .begin(async move |ctx| -> Result<(bool, bool), LinkManagerError> {
+                let Some(state) = self
+                    .persistence_repo
+                    .find_link_health_state(link_id, ctx.clone())
+                    .await?
+                else {
+                    // purged while the check was running
+                    return Ok((false, false));
+                };
+
+                let next_state = state.after(&check, config.failure_threshold);
+                let next_check_at =
+                    check.checked_at + next_health_check_delay(&next_state, &check, config);
+
+                self.persistence_repo
+                    .save_health_check(link_id, &check, ctx.clone())
+                    .await?;
+                self.persistence_repo
+                    .save_link_health_state(link_id, &next_state, next_check_at, ctx.clone())
+                    .await?;
+
+                Ok((
+                    state.broken_since.is_some(),
+                    next_state.broken_since.is_some(),
+                ))
+            })
+            .await?;
+
+        if was_broken != is_broken {
+            if is_broken {
+                println!("link {link_id} is broken");
+            } else {
+                println!("link {link_id} recovered");
+            }
+            self.invalidate_cached_link(link_id).await;
+        }
+
+        Ok(())
+    }
+
+    /// Health of every link of the user.
+    pub async fn get_health_report(
+        &self,
+        user_id: i32,
+    ) -> Result<Vec<LinkHealth>, LinkManagerError> {
+        let report = self
+            .persistence_repo
+            .find_link_health_by_user_id(user_id, TrxContext::Empty)
+            .await?;
+
+        Ok(report)
+    }
+
+    /// Latest health checks of the link, with status codes, redirect chains and latency.
+    pub async fn get_link_health_checks(
+        &self,
+        link_id: &LinkId,
+        user_id: i32,
+    ) -> Result<Vec<HealthCheck>, LinkManagerError> {
+        let link = self
+            .persistence_repo
+            .find_link_by_id(link_id, TrxContext::Empty)
+            .await?
+            .filter(|link| link.deleted_at.is_none())
+            .ok_or(LinkManagerError::LinkNotFound(link_id.clone()))?;
+
+        if link.user_id != user_id {
+            return Err(LinkManagerError::LinkNotOwnedByUser(
+                link_id.clone(),
+                user_id,
+            ));
+        }
+
+        let checks = self
+            .persistence_repo
+            .find_health_checks(link_id, HEALTH_CHECK_HISTORY_LIMIT, TrxContext::Empty)
+            .await?;
+
+        Ok(checks)
+    }
+
+    /// Deletes health checks older than `retention`.
+    pub async fn prune_health_checks(
+        &self,
+        retention: chrono::Duration,
+    ) -> Result<u64, LinkManagerError> {
+        let checked_before = chrono::Utc::now() - retention;
+        let pruned = self
+            .trx_factory
+            .begin(async move |ctx| -> Result<u64, LinkManagerError> {
+                let pruned = self
+                    .persistence_repo
+                    .delete_health_checks_before(checked_before, ctx.clone())
+                    .await?;
+                Ok(pruned)
+            })
+            .await?;
+
+        Ok(pruned)
+    }
+}
+
+/// An existing link is only reused if it behaves like the requested one. Links created
+/// without a label get the page title, so a request without one matches any label.
+fn is_reusable_for(link: &Link, new_link: &NewLink) -> bool {
+    (new_link.label.is_empty() || link.label == new_link.label)
+        && link.active_from == new_link.active_from
+        && link.options == new_link.options
+}
+
+/// Whether the destination depends on the request, or on the link health for a fallback.
+fn varies_per_visitor(link: &Link) -> bool {
+    let options = &link.options;
+    !options.routing.is_empty()
+        || !options.geo.is_empty()
+        || !options.languages.is_empty()
+        || !options.variants.is_empty()
+        || options.forward_path
+        || options.forward_query
+        || options.fallback_url.is_some()
+}
+
+/// Trashed, not yet active and used up one-time links can not be visited.
+fn check_visitable(link: &Link, redirect_ctx: &RedirectContext) -> Result<(), LinkManagerError> {
+    if link.deleted_at.is_some() {
+        return Err(LinkManagerError::LinkNotFound(link.id.clone()));
+    }
+    if let Some(active_from) = link.active_from
+        && !link.is_active_at(redirect_ctx.at)
+    {
+        return Err(LinkManagerError::NotYetActive(active_from));
+    }
+    if link.options.one_time && link.consumed_at.is_some() {
+        return Err(LinkManagerError::LinkConsumed(link.id.clone()));
+    }
+
+    Ok(())
+}
+
+/// Healthy links wait a full check interval, failed ones are retried after
+/// `retry_backoff_sec`, doubled for every further failure up to `max_backoff_sec`.
+fn next_health_check_delay(
+    state: &LinkHealthState,
+    check: &HealthCheck,
+    config: &HealthCheckConfig,
+) -> chrono::Duration {
+    let delay_sec = match check.outcome {
+        HealthOutcome::Healthy => config.check_interval_sec,
+        HealthOutcome::Throttled => config.retry_backoff_sec,
+        HealthOutcome::Broken => {
+            let doublings = (state.failures - 1).clamp(0, 30) as u32;
+            config
+                .retry_backoff_sec
+                .saturating_mul(1 << doublings)
+                .min(config.max_backoff_sec)
+        }
+    };
+
+    chrono::Duration::seconds(delay_sec.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::{
+        domain::link_manager::{
+            entity::{
+                geo_rule::GeoRule,
+                language_rules::LanguageRules,
+                link_options::LinkOptions,
+                routing_rules::{OsRule, RoutingRules},
+                schedule::{Schedule, TimeWindow},
+            },
+            infra::in_memory::{InMemoryLinkCache, InMemoryLinkManagerRepo},
+        },
+        tools::{in_memory_trx::InMemoryTrxFactory, user_agent::Os},
+    };
+
+    type Service = LinkManagerService<InMemoryLinkManagerRepo, InMemoryTrxFactory>;
+
+    const USER_ID: i32 = 1;
+    const OTHER_USER_ID: i32 = 2;
+
+    fn link_manager_service(link_cache: Arc<dyn LinkCache>) -> Service {
+        LinkManagerService::new(
+            InMemoryLinkManagerRepo::new(),
+            InMemoryTrxFactory,
+            link_cache,
+            3600,
+            86400,
+            LinksConfig::default(),
+        )
+        .with_cache_config(&CacheConfig::default())
+    }
+
+    async fn create_link(service: &Service, new_link: NewLink) -> LinkId {
+        service
+            .create_link(USER_ID, new_link, false, None)
+            .await
+            .unwrap()
+    }
+
+    async fn view(service: &Service, link_id: &LinkId) -> Result<Link, LinkManagerError> {
+        service
+            .view_link(link_id, &RedirectContext::new(None, None))
+            .await
+    }
+
+    fn new_link(redirect_url: &str) -> NewLink {
+        NewLink::new(redirect_url.to_string(), "docs".to_string())
+    }
+
+    #[tokio::test]
+    async fn created_link_redirects_to_destination() {
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::new()));
+        let link_id = create_link(&service, new_link("https://example.com/docs")).await;
+
+        let link = view(&service, &link_id).await.unwrap();
+        let destination = service.resolve_destination(&link, &RedirectContext::new(None, None));
+
+        assert_eq!(destination, "https://example.com/docs");
+        assert_eq!(service.get_link_views(&link_id).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn permanent_redirect_is_only_cacheable_without_per_visitor_rules() {
+        let routing = RoutingRules {
+            os: vec![OsRule {
+                os: Os::Ios,
+                destination: "https://example.com/ios".to_string(),
+            }],
+            ..RoutingRules::default()
+        };
+        let geo = vec![GeoRule {
+            country: "DE".to_string(),
+            region: None,
+            destination: "https://example.com/de".to_string(),
+        }];
+        let languages = LanguageRules {
+            locales: [("de".to_string(), "https://example.com/de".to_string())].into(),
+            default: None,
+        };
+        let variants = ["a", "b"]
+            .into_iter()
+            .map(|name| Variant {
+                name: name.to_string(),
+                destination: format!("https://example.com/{name}"),
+                weight: 1,
+            })
+            .collect();
+        let per_visitor_cases = [
+            (
+                "routing",
+                LinkOptions {
+                    routing,
+                    ..LinkOptions::default()
+                },
+            ),
+            (
+                "geo",
+                LinkOptions {
+                    geo,
+                    ..LinkOptions::default()
+                },
+            ),
+            (
+                "languages",
+                LinkOptions {
+                    languages,
+                    ..LinkOptions::default()
+                },
+            ),
+            (
+                "variants",
+                LinkOptions {
+                    variants,
+                    ..LinkOptions::default()
+                },
+            ),
+            (
+                "forward_path",
+                LinkOptions {
+                    forward_path: true,
+                    ..LinkOptions::default()
+                },
+            ),
+            (
+                "forward_query",
+                LinkOptions {
+                    forward_query: true,
+                    ..LinkOptions::default()
+                },
+            ),
+            (
+                "fallback_url",
+                LinkOptions {
+                    fallback_url: Some("https://example.com/fallback".to_string()),
+                    ..LinkOptions::default()
+                },
+            ),
+        ];
+        let cacheable = RedirectType::MovedPermanently.cache_control();
+        let cases = [("plain", LinkOptions::default(), cacheable)]
+            .into_iter()
+            .chain(
+                per_visitor_cases
+                    .into_iter()
+                    .map(|(name, options)| (name, options, "private, no-store".to_string())),
+            );
+
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::new()));
+        for (name, options, expected) in cases {
+            let options = LinkOptions {
+                redirect_type: Some(RedirectType::MovedPermanently),
+                ..options
+            };
+            let link_id = create_link(
+                &service,
+                new_link("https://example.com").with_options(options),
+            )
+            .await;
+
+            let link = view(&service, &link_id).await.unwrap();
+            assert_eq!(service.cache_control(&link), expected, "{name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn create_link_rejects_invalid_destination() {
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::new()));
+        let options = LinkOptions {
+            fallback_url: Some("ftp://example.com".to_string()),
+            ..LinkOptions::default()
+        };
+
+        let result = service
+            .create_link(
+                USER_ID,
+                new_link("https://example.com").with_options(options),
+                false,
+                None,
+            )
+            .await;
+
+        assert!(matches!(result, Err(LinkManagerError::InvalidOptions(_))));
+    }
+
+    #[tokio::test]
+    async fn create_link_rejects_non_http_redirect_url() {
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::new()));
+
+        let result = service
+            .create_link(USER_ID, new_link("javascript:alert(1)"), false, None)
+            .await;
+
+        assert!(matches!(result, Err(LinkManagerError::InvalidOptions(_))));
+    }
+
+    #[tokio::test]
+    async fn create_link_replays_idempotency_key() {
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::new()));
+        let key = || IdempotencyKey::new("key".to_string(), "fingerprint".to_string());
+
+        let first = service
+            .create_link(USER_ID, new_link("https://example.com"), false, Some(key()))
+            .await
+            .unwrap();
+        let second = service
+            .create_link(USER_ID, new_link("https://example.com"), false, Some(key()))
+            .await
+            .unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(service.get_user_links(USER_ID).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn purge_deletes_expired_idempotency_records_in_batches() {
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::new()));
+        let link_id = create_link(&service, new_link("https://example.com")).await;
+        let record = |key: String, expires_in: chrono::Duration| {
+            IdempotencyRecord::from_parts(
+                USER_ID,
+                key,
+                "fingerprint".to_string(),
+                link_id.clone(),
+                chrono::Utc::now(),
+                chrono::Utc::now() + expires_in,
+            )
+        };
+        let expired = IDEMPOTENCY_PURGE_BATCH_SIZE as u64 + 1;
+        for i in 0..expired {
+            service
+                .persistence_repo
+                .save_idempotency_record(
+                    record(format!("expired-{i}"), chrono::Duration::hours(-1)),
+                    TrxContext::Empty,
+                )
+                .await
+                .unwrap();
+        }
+        service
+            .persistence_repo
+            .save_idempotency_record(
+                record("live".to_string(), chrono::Duration::hours(1)),
+                TrxContext::Empty,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            service.purge_expired_idempotency_records().await.unwrap(),
+            expired
+        );
+        for (key, kept) in [("expired-0", false), ("live", true)] {
+            let found = service
+                .persistence_repo
+                .find_idempotency_record(USER_ID, key, TrxContext::Empty)
+                .await
+                .unwrap();
+            assert_eq!(found.is_some(), kept, "{key}");
+        }
+    }
+
+    #[tokio::test]
+    async fn new_link_never_overwrites_one_with_the_same_id() {
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::new()));
+        let link_id = create_link(&service, new_link("https://example.com/docs")).await;
+        let colliding = Link::new(
+            link_id.clone(),
+            OTHER_USER_ID,
+            "https://example.com/elsewhere".to_string(),
+            "elsewhere".to_string(),
+            None,
+            None,
+            LinkOptions::default(),
+        );
+
+        assert!(
+            service
+                .persistence_repo
+                .insert_link(colliding, TrxContext::Empty)
+                .await
+                .is_err()
+        );
+        let link = view(&service, &link_id).await.unwrap();
+        assert_eq!(link.user_id, USER_ID);
+        assert_eq!(link.redirect_url, "https://example.com/docs");
+    }
+
+    #[tokio::test]
+    async fn open_time_window_wins_over_language_default() {
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::new()));
+        let options = LinkOptions {
+            languages: LanguageRules {
+                locales: [("de".to_string(), "https://example.com/de".to_string())].into(),
+                default: Some("https://example.com/en".to_string()),
+            },
+            schedule: Schedule {
+                timezone: None,
+                windows: vec![TimeWindow {
+                    days: vec![],
+                    start: chrono::NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                    end: chrono::NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+                    destination: "https://example.com/office-hours".to_string(),
+                }],
+            },
+            ..LinkOptions::default()
+        };
+        let link_id = create_link(
+            &service,
+            new_link("https://example.com").with_options(options),
+        )
+        .await;
+        let link = view(&service, &link_id).await.unwrap();
+
+        let cases = [
+            ("fr", 12, "https://example.com/office-hours"),
+            ("fr", 20, "https://example.com/en"),
+            ("de", 12, "https://example.com/de"),
+        ];
+        for (language, hour, expected) in cases {
+            let mut redirect_ctx =
+                RedirectContext::new(None, None).with_accept_language(Some(language.to_string()));
+            redirect_ctx.at = chrono::Utc
+                .with_ymd_and_hms(2025, 6, 2, hour, 0, 0)
+                .unwrap();
+            assert_eq!(
+                service.resolve_destination(&link, &redirect_ctx),
+                expected,
+                "{language} at {hour}:00"
+            );
+        }
+    }
+
+    async fn reuse(service: &Service, new_link: NewLink) -> LinkId {
+        service
+            .create_link(USER_ID, new_link, true, None)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn reuse_existing_returns_the_link_without_tracking_params() {
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::new()));
+        let link_id = reuse(&service, new_link("https://example.com/docs?page=2")).await;
+
+        let reused = reuse(
+            &service,
+            new_link("https://EXAMPLE.com/docs/?utm_source=mail&page=2&fbclid=x#intro"),
+        )
+        .await;
+
+        assert_eq!(reused, link_id);
+    }
+
+    #[tokio::test]
+    async fn reuse_existing_skips_links_that_behave_differently() {
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::new()));
+        let link_id = reuse(&service, new_link("https://example.com")).await;
+
+        let cases = [
+            (
+                "label",
+                NewLink::new("https://example.com".to_string(), "other".to_string()),
+            ),
+            (
+                "active_from",
+                new_link("https://example.com").with_active_from(Some(chrono::Utc::now())),
+            ),
+            (
+                "redirect_type",
+                new_link("https://example.com").with_options(LinkOptions {
+                    redirect_type: Some(RedirectType::MovedPermanently),
+                    ..LinkOptions::default()
+                }),
+            ),
+            (
+                "one_time",
+                new_link("https://example.com").with_options(LinkOptions {
+                    one_time: true,
+                    ..LinkOptions::default()
+                }),
+            ),
+        ];
+        for (name, new_link) in cases {
+            assert_ne!(reuse(&service, new_link).await, link_id, "{name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn reuse_existing_skips_used_up_and_trashed_links() {
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::new()));
+        let one_time = || {
+            new_link("https://example.com/once").with_options(LinkOptions {
+                one_time: true,
+                ..LinkOptions::default()
+            })
+        };
+        let consumed = reuse(&service, one_time()).await;
+        view(&service, &consumed).await.unwrap();
+        let trashed = reuse(&service, new_link("https://example.com/trashed")).await;
+        service.delete_link(trashed.clone(), USER_ID).await.unwrap();
+
+        assert_ne!(reuse(&service, one_time()).await, consumed);
+        assert_ne!(
+            reuse(&service, new_link("https://example.com/trashed")).await,
+            trashed
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_link_is_not_found() {
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::new()));
+
+        let result = view(&service, &LinkId::from_string("nope".to_string())).await;
+
+        assert!(matches!(result, Err(LinkManagerError::LinkNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn deleted_link_stops_redirecting_although_cached() {
+        let link_cache = Arc::new(InMemoryLinkCache::new());
+        let service = link_manager_service(link_cache.clone());
+        let link_id = create_link(&service, new_link("https://example.com")).await;
+        view(&service, &link_id).await.unwrap();
+
+        service.delete_link(link_id.clone(), USER_ID).await.unwrap();
+
+        assert!(link_cache.cached(&link_id).is_none());
+        assert!(matches!(
+            view(&service, &link_id).await,
+            Err(LinkManagerError::LinkNotFound(_))
+        ));
+        assert_eq!(service.get_trashed_links(USER_ID).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn delete_link_requires_owner() {
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::new()));
+        let link_id = create_link(&service, new_link("https://example.com")).await;
+
+        let result = service.delete_link(link_id.clone(), OTHER_USER_ID).await;
+
+        assert!(matches!(
+            result,
+            Err(LinkManagerError::LinkNotOwnedByUser(_, OTHER_USER_ID))
+        ));
+        assert!(view(&service, &link_id).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn restored_link_redirects_again() {
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::new()));
+        let link_id = create_link(&service, new_link("https://example.com")).await;
+        service.delete_link(link_id.clone(), USER_ID).await.unwrap();
+
+        service.restore_link(&link_id, USER_ID).await.unwrap();
+
+        assert!(view(&service, &link_id).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn one_time_link_redirects_once() {
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::new()));
+        let options = LinkOptions {
+            one_time: true,
+            ..LinkOptions::default()
+        };
+        let link_id = create_link(
+            &service,
+            new_link("https://example.com").with_options(options),
+        )
+        .await;
+
+        assert!(view(&service, &link_id).await.is_ok());
+        assert!(matches!(
+            view(&service, &link_id).await,
+            Err(LinkManagerError::LinkConsumed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn redirects_work_without_cache() {
+        let service = link_manager_service(Arc::new(InMemoryLinkCache::unavailable()));
+        let link_id = create_link(&service, new_link("https://example.com")).await;
+
+        assert!(view(&service, &link_id).await.is_ok());
+        service.delete_link(link_id.clone(), USER_ID).await.unwrap();
+        assert!(view(&service, &link_id).await.is_err());
+        assert_eq!(
+            service.cache_stats().redis_status,
+            RedisStatus::Disconnected
+        );
+    }
+
+    #[tokio::test]
+    async fn cache_rejects_write_back_after_invalidation() {
+        let link_cache = InMemoryLinkCache::new();
+        let link_id = LinkId::from_string("race".to_string());
+        let CacheLookup::Miss(version) = link_cache.get(&link_id).await else {
+            panic!("expected a miss");
+        };
+
+        link_cache.invalidate(&link_id).await;
+        link_cache.put(&link_id, None, 60, version).await;
+
+        assert!(link_cache.cached(&link_id).is_none());
+    }
 }
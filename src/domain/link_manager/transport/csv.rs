@@ -0,0 +1,175 @@
+use utoipa::ToSchema;
+
+use crate::domain::link_manager::entity::{
+    link::Link,
+    new_link::{LinkRowError, NewLink},
+};
+
+// Bitly exports use "long_url" / "title", our own export uses "redirect_url" / "label".
+const URL_COLUMNS: [&str; 5] = [
+    "redirect_url",
+    "redirected_url",
+    "long_url",
+    "url",
+    "destination",
+];
+const LABEL_COLUMNS: [&str; 3] = ["label", "title", "name"];
+/// Spreadsheets evaluate cells starting with one of these as formulas.
+const FORMULA_PREFIXES: [char; 4] = ['=', '+', '-', '@'];
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct LinkExportRow {
+    pub id: String,
+    pub redirect_url: String,
+    pub label: String,
+    pub views: i64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_view: Option<chrono::DateTime<chrono::Utc>>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub favicon: Option<String>,
+    pub image: Option<String>,
+}
+
+impl From<Link> for LinkExportRow {
+    fn from(link: Link) -> Self {
+        Self {
+            id: link.id.to_string(),
+            redirect_url: link.redirect_url.clone(),
+            label: link.label.clone(),
+            views: link.views,
+            created_at: link.created_at,
+            last_view: link.last_view,
+            title: link.metadata.as_ref().and_then(|m| m.title.clone()),
+            description: link.metadata.as_ref().and_then(|m| m.description.clone()),
+            favicon: link.metadata.as_ref().and_then(|m| m.favicon.clone()),
+            image: link.metadata.as_ref().and_then(|m| m.image.clone()),
+        }
+    }
+}
+
+impl LinkExportRow {
+    fn escape_formulas(self) -> Self {
+        Self {
+            redirect_url: escape_formula(self.redirect_url),
+            label: escape_formula(self.label),
+            title: self.title.map(escape_formula),
+            description: self.description.map(escape_formula),
+            favicon: self.favicon.map(escape_formula),
+            image: self.image.map(escape_formula),
+            ..self
+        }
+    }
+}
+
+/// Prefixes a quote, so spreadsheets show the text instead of evaluating it.
+fn escape_formula(cell: String) -> String {
+    if cell.starts_with(FORMULA_PREFIXES) {
+        format!("'{cell}")
+    } else {
+        cell
+    }
+}
+
+/// Undoes `escape_formula`, so an export imports unchanged.
+fn unescape_formula(cell: &str) -> &str {
+    match cell.strip_prefix('\'') {
+        Some(formula) if formula.starts_with(FORMULA_PREFIXES) => formula,
+        _ => cell,
+    }
+}
+
+fn normalize_header(header: &str) -> String {
+    header.trim().to_lowercase().replace([' ', '-'], "_")
+}
+
+fn find_column(headers: &[String], candidates: &[&str]) -> Option<usize> {
+    candidates
+        .iter()
+        .find_map(|candidate| headers.iter().position(|h| h == candidate))
+}
+
+/// Parses an uploaded CSV into new links. Rows are numbered from 1, header excluded.
+/// Cells escaped by `write_links_csv` are read back unescaped.
+pub fn parse_links_csv(body: &str) -> Result<Vec<NewLink>, Vec<LinkRowError>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(body.as_bytes());
+
+    let headers: Vec<String> = match reader.headers() {
+        Ok(headers) => headers.iter().map(normalize_header).collect(),
+        Err(e) => return Err(vec![LinkRowError::new(0, format!("invalid header: {e}"))]),
+    };
+
+    let Some(url_column) = find_column(&headers, &URL_COLUMNS) else {
+        return Err(vec![LinkRowError::new(
+            0,
+            format!(
+                "missing url column, expected one of: {}",
+                URL_COLUMNS.join(", ")
+            ),
+        )]);
+    };
+    let label_column = find_column(&headers, &LABEL_COLUMNS);
+
+    let mut new_links = Vec::new();
+    let mut row_errors = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let row = i + 1;
+        let record = match record {
+            Ok(r) => r,
+            Err(e) => {
+                row_errors.push(LinkRowError::new(row, format!("invalid row: {e}")));
+                continue;
+            }
+        };
+
+        let Some(redirect_url) = record.get(url_column) else {
+            row_errors.push(LinkRowError::new(row, "missing url".to_string()));
+            continue;
+        };
+        let label = label_column.and_then(|c| record.get(c)).unwrap_or_default();
+
+        new_links.push(NewLink::new(
+            unescape_formula(redirect_url).to_string(),
+            unescape_formula(label).to_string(),
+        ));
+    }
+
+    if !row_errors.is_empty() {
+        return Err(row_errors);
+    }
+
+    Ok(new_links)
+}
+
+/// Cells that a spreadsheet would evaluate are prefixed with a quote.
+pub fn write_links_csv(links: Vec<Link>) -> eyre::Result<String> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(vec![]);
+
+    // written by hand so an empty export still carries the header
+    writer.write_record([
+        "id",
+        "redirect_url",
+        "label",
+        "views",
+        "created_at",
+        "last_view",
+        "title",
+        "description",
+        "favicon",
+        "image",
+    ])?;
+    for link in links {
+        writer.serialize(LinkExportRow::from(link).escape_formulas())?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| eyre::eyre!("failed to flush csv: {e}"))?;
+
+    Ok(String::from_utf8(bytes)?)
+}
@@ -0,0 +1,119 @@
+use crate::domain::link_manager::entity::{link::Link, new_link::validate_destination};
+
+pub fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escaping does not defuse a `javascript:` url, so only http(s) destinations are linked.
+fn destination_link(destination: &str, attributes: &str, text: &str) -> String {
+    if validate_destination(destination).is_err() {
+        return text.to_string();
+    }
+    format!(
+        r#"<a href="{}"{attributes}>{text}</a>"#,
+        escape_html(destination)
+    )
+}
+
+/// Shows where a short link goes without following it.
+pub fn preview_page(link: &Link) -> String {
+    let label = escape_html(&link.label);
+    let destination = escape_html(&link.redirect_url);
+    let destination_link = destination_link(
+        &link.redirect_url,
+        r#" rel="noopener noreferrer""#,
+        &destination,
+    );
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="robots" content="noindex">
+<title>Preview: {label}</title>
+</head>
+<body>
+<h1>{label}</h1>
+<p>This short link leads to:</p>
+<p>{destination_link}</p>
+<p>Created {created_at}</p>
+</body>
+</html>"#,
+        created_at = link.created_at.format("%Y-%m-%d"),
+    )
+}
+
+/// Open Graph and Twitter card tags for crawlers, with a meta refresh for anyone else who
+/// lands on the page. The card of a one-time link leaves out the destination, it is only
+/// revealed to the visitor who uses the link up.
+pub fn social_card_page(link: &Link, destination: &str) -> String {
+    let og = &link.options.og;
+    let title = escape_html(og.title.as_deref().unwrap_or(&link.label));
+    let destination = (!link.options.one_time).then_some(destination);
+    let destination_link = match destination {
+        Some(destination) => destination_link(destination, "", &title),
+        None => title.clone(),
+    };
+
+    let mut meta = vec![
+        format!(r#"<meta property="og:title" content="{title}">"#),
+        r#"<meta property="og:type" content="website">"#.to_string(),
+        format!(r#"<meta name="twitter:title" content="{title}">"#),
+    ];
+    if let Some(destination) = destination
+        && validate_destination(destination).is_ok()
+    {
+        let destination = escape_html(destination);
+        meta.push(format!(
+            r#"<meta property="og:url" content="{destination}">"#
+        ));
+        meta.push(format!(
+            r#"<meta http-equiv="refresh" content="0; url={destination}">"#
+        ));
+    }
+    if let Some(description) = &og.description {
+        let description = escape_html(description);
+        meta.push(format!(
+            r#"<meta property="og:description" content="{description}">"#
+        ));
+        meta.push(format!(
+            r#"<meta name="twitter:description" content="{description}">"#
+        ));
+    }
+    match &og.image {
+        Some(image) => {
+            let image = escape_html(image);
+            meta.push(format!(r#"<meta property="og:image" content="{image}">"#));
+            meta.push(format!(r#"<meta name="twitter:image" content="{image}">"#));
+            meta.push(r#"<meta name="twitter:card" content="summary_large_image">"#.to_string());
+        }
+        None => meta.push(r#"<meta name="twitter:card" content="summary">"#.to_string()),
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+{meta}
+</head>
+<body>
+<p>{destination_link}</p>
+</body>
+</html>"#,
+        meta = meta.join("\n"),
+    )
+}
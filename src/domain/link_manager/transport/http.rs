@@ -1,11 +1,102 @@
 use axum::{
-    extract::{Path, State}, http::StatusCode, Extension, response:: Redirect, Json
+    extract::{ConnectInfo, Path, Query, RawQuery, State}, http::{header, HeaderMap, HeaderValue, StatusCode}, Extension, response::{Html, IntoResponse, Response}, Json
 };
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use sha2::{Digest, Sha256};
+use std::net::SocketAddr;
 use utoipa::ToSchema;
 
-use crate::{domain::link_manager::{entity::link::LinkId, service::LinkManagerError}, transport::http::auth::MiddlewareUserResponse, AppState};
+use crate::{tools::cache_stats::CacheStats, domain::link_manager::{entity::{geo_rule::GeoRule, idempotency_key::IdempotencyKey, language_rules::LanguageRules, link::LinkId, link_health::HealthCheck, link_stats::{CountryViews, VariantViews}, link_options::LinkOptions, new_link::{LinkRowError, NewLink}, open_graph::OpenGraph, redirect_context::RedirectContext, redirect_type::RedirectType, routing_rules::RoutingRules, schedule::Schedule, utm::UtmParams, variant::Variant}, service::LinkManagerError}, transport::http::auth::MiddlewareUserResponse, AppState};
+
+use super::csv::{parse_links_csv, write_links_csv, LinkExportRow};
+use super::html::{preview_page, social_card_page};
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ViewLinkPath{
+    link_id: String,
+    rest: Option<String>,
+}
+
+const VARIANT_COOKIE_MAX_AGE_DAYS: i64 = 30;
+
+fn variant_cookie_name(link_id: &str) -> String {
+    format!("variant_{link_id}")
+}
+
+fn redirect_context(state: &AppState, link_id: &str, query: Option<String>, rest: Option<String>, peer: SocketAddr, jar: &CookieJar, headers: &HeaderMap) -> RedirectContext {
+    let user_agent = headers.get(header::USER_AGENT).and_then(|v| v.to_str().ok()).map(String::from);
+    let accept_language = headers.get(header::ACCEPT_LANGUAGE).and_then(|v| v.to_str().ok()).map(String::from);
+    let client_ip = state.client_ip_resolver.resolve(peer.ip(), headers);
+    let geo = state.geo_ip_resolver.lookup(client_ip);
+
+    RedirectContext::new(query, rest)
+        .with_user_agent(user_agent)
+        .with_accept_language(accept_language)
+        .with_client(client_ip, geo)
+        .with_variant(jar.get(&variant_cookie_name(link_id)).map(|c| c.value().to_string()))
+}
+
+fn redirect_response(redirect_type: RedirectType, cache_control: String, destination: &str) -> Result<Response, StatusCode> {
+    let location = HeaderValue::try_from(destination).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let cache_control = HeaderValue::try_from(cache_control).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((
+        redirect_type.status_code(),
+        [
+            (header::LOCATION, location),
+            (header::CACHE_CONTROL, cache_control),
+        ],
+    ).into_response())
+}
+
+fn not_yet_active_response(active_from: chrono::DateTime<chrono::Utc>) -> Response {
+    let body = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Not yet available</title></head>\
+        <body><h1>This link is not available yet</h1><p>Check back after {}.</p></body></html>",
+        active_from.format("%Y-%m-%d %H:%M UTC"),
+    );
+
+    (
+        StatusCode::NOT_FOUND,
+        [(header::CACHE_CONTROL, HeaderValue::from_static("no-store"))],
+        Html(body),
+    ).into_response()
+}
 
 /// View short link 
+///
+/// Responds with the link redirect type, or the configured default (303). Permanent
+/// redirects (301, 308) are cacheable, temporary ones (302, 303, 307) are sent with
+/// `Cache-Control: no-store` so every click is counted. Links whose destination depends on
+/// the visitor (routing, geo, language, variants, forwarding or a fallback) are sent with
+/// `private, no-store` whatever their type.
+///
+/// Links with `forward_path` append anything after the link id (`/view/{link_id}/docs/page`)
+/// to the destination path. Links with `forward_query` pass the query string on; on a key
+/// conflict the destination value wins. UTM params are applied last and never override
+/// params already present.
+///
+/// Links with A/B variants pick a weighted variant per visitor and keep it in a
+/// `variant_{link_id}` cookie, so returning visitors see the same page.
+///
+/// Links with locales pick the destination best matching the `Accept-Language` header,
+/// honouring quality values, and fall back to their default locale destination when no
+/// time window is open.
+///
+/// Link preview crawlers (configurable User-Agent list) get an HTML page with the link's
+/// Open Graph tags and a meta refresh instead of the redirect, if the link has a social card.
+/// Their hits are never counted and never use up a one-time link; one-time links without a
+/// social card answer them with 403, the card of a one-time link leaves out its destination.
+///
+/// Public, visitors and crawlers need no authentication.
+///
+/// A `+` after the link id (`/view/{link_id}+`) shows the preview page instead.
+///
+/// One-time links redirect once; the first hit consumes them atomically and every later
+/// hit gets 410 Gone.
+///
+/// Links with an `active_from` in the future answer with a "not yet available" page.
+/// Links with time-window rules are never cacheable.
 #[utoipa::path(
     get, 
     path = "/view/{link_id}", 
@@ -14,17 +105,69 @@ use crate::{domain::link_manager::{entity::link::LinkId, service::LinkManagerErr
     ),
     tag = "short-link",
     responses(
-        (status = 200, description = "OK" ),
-        (status = 404, description = "Not Found"),
+        (status = 200, description = "Social card page for link preview crawlers", content_type = "text/html"),
+        (status = 301, description = "Moved Permanently", headers(("Location" = String), ("Cache-Control" = String))),
+        (status = 302, description = "Found", headers(("Location" = String), ("Cache-Control" = String))),
+        (status = 303, description = "See Other", headers(("Location" = String), ("Cache-Control" = String))),
+        (status = 307, description = "Temporary Redirect", headers(("Location" = String), ("Cache-Control" = String))),
+        (status = 308, description = "Permanent Redirect", headers(("Location" = String), ("Cache-Control" = String))),
+                (status = 403, description = "One-time link without a social card, for link preview crawlers"),
+        (status = 404, description = "Not Found, or not yet available", content_type = "text/html"),
+        (status = 410, description = "One-time link already used"),
         (status = 500, description = "Internal Server Error"),)
 )]
 pub async fn view_link_get_handler(
     State(state): State<AppState>,
-    Path(link_id): Path<String>,
-) -> Result<Redirect, StatusCode> {
-    match state.link_manager_service.view_link(&LinkId::from_string(link_id)).await{
-        Ok(link) => 
-             Ok(Redirect::to(&link.redirect_url)),
+    Path(path): Path<ViewLinkPath>,
+    RawQuery(query): RawQuery,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    jar: CookieJar,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    if let Some(link_id) = path.link_id.strip_suffix('+') {
+        return preview_response(&state, link_id).await;
+    }
+
+    let cookie_name = variant_cookie_name(&path.link_id);
+    let redirect_ctx = redirect_context(&state, &path.link_id, query, path.rest, peer, &jar, &headers);
+
+    let link_id = LinkId::from_string(path.link_id.clone());
+    let viewed = if state.link_manager_service.is_crawler(&redirect_ctx) {
+        state.link_manager_service.crawler_view(&link_id, &redirect_ctx).await
+    } else {
+        state.link_manager_service.view_link(&link_id, &redirect_ctx).await
+    };
+    match viewed {
+        Ok(link) => {
+            let destination = state.link_manager_service.resolve_destination(&link, &redirect_ctx);
+            if state.link_manager_service.wants_social_card(&link, &redirect_ctx) {
+                let cache_control = HeaderValue::try_from(state.link_manager_service.cache_control(&link)).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                return Ok((
+                    [(header::CACHE_CONTROL, cache_control)],
+                    Html(social_card_page(&link, &destination)),
+                ).into_response());
+            }
+
+            let response = redirect_response(state.link_manager_service.redirect_type(&link), state.link_manager_service.cache_control(&link), &destination)?;
+
+            let Some(variant) = state.link_manager_service.choose_variant(&link, &redirect_ctx) else {
+                return Ok(response);
+            };
+            let cookie = Cookie::build((cookie_name, variant.name.clone()))
+                .path(format!("/view/{}", path.link_id))
+                .http_only(true)
+                .same_site(SameSite::Lax)
+                .max_age(time::Duration::days(VARIANT_COOKIE_MAX_AGE_DAYS))
+                .build();
+
+            Ok((jar.add(cookie), response).into_response())
+        }
+        Err(LinkManagerError::LinkConsumed(_)) => 
+            Err(StatusCode::GONE),
+        Err(LinkManagerError::PreviewNotAllowed(_)) => 
+            Err(StatusCode::FORBIDDEN),
+        Err(LinkManagerError::NotYetActive(active_from)) => 
+            Ok(not_yet_active_response(active_from)),
         Err(LinkManagerError::LinkNotFound(_)) => 
             Err(
                 StatusCode::NOT_FOUND,
@@ -34,6 +177,102 @@ pub async fn view_link_get_handler(
     }
 }
 
+async fn preview_response(state: &AppState, link_id: &str) -> Result<Response, StatusCode> {
+    match state.link_manager_service.preview_link(&LinkId::from_string(link_id.to_string())).await{
+        Ok(link) => 
+            Ok(Html(preview_page(&link)).into_response()),
+        Err(LinkManagerError::NotYetActive(active_from)) => 
+            Ok(not_yet_active_response(active_from)),
+        Err(LinkManagerError::LinkNotFound(_)) => 
+            Err(StatusCode::NOT_FOUND),
+        Err(LinkManagerError::PreviewNotAllowed(_)) => 
+            Err(StatusCode::FORBIDDEN),
+        Err(_) => 
+            Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Preview short link
+///
+/// Shows the destination, label and creation date without redirecting or counting a view.
+/// Also served for `/view/{link_id}+`. One-time links can not be previewed.
+#[utoipa::path(
+    get, 
+    path = "/preview/{link_id}", 
+    params(
+        ("link_id" = String, Path, description = "ID of the link", example = "SVa-")
+    ),
+    tag = "short-link",
+    responses(
+        (status = 200, description = "OK", content_type = "text/html"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "One-time link"),
+        (status = 404, description = "Not Found, or not yet available"),
+        (status = 500, description = "Internal Server Error"),)
+)]
+pub async fn preview_link_get_handler(
+    State(state): State<AppState>,
+    Path(link_id): Path<String>,
+) -> Result<Response, StatusCode> {
+    preview_response(&state, &link_id).await
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct ExpandLinkResponse{
+    id: String,
+    /// The stored destination, before any rule is applied.
+    destination: String,
+    /// Where `/view/{link_id}` would send this caller.
+    resolved_destination: String,
+    label: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Expand short link
+///
+/// Returns the destination without redirecting or counting a view. `resolved_destination`
+/// has the routing, geo, language, time-window and variant rules, the fallback of a broken
+/// link and the UTM params applied, for the caller's User-Agent, language and address.
+#[utoipa::path(
+    get, 
+    path = "/expand/{link_id}", 
+    params(
+        ("link_id" = String, Path, description = "ID of the link", example = "SVa-")
+    ),
+    tag = "short-link",
+    responses(
+        (status = 200, description = "OK", body = ExpandLinkResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "One-time link"),
+        (status = 404, description = "Not Found, or not yet available"),
+        (status = 500, description = "Internal Server Error"),)
+)]
+pub async fn expand_link_get_handler(
+    State(state): State<AppState>,
+    Path(link_id): Path<String>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    jar: CookieJar,
+    headers: HeaderMap,
+) -> Result<Json<ExpandLinkResponse>, StatusCode> {
+    let redirect_ctx = redirect_context(&state, &link_id, None, None, peer, &jar, &headers);
+    match state.link_manager_service.preview_link(&LinkId::from_string(link_id)).await{
+        Ok(link) => 
+            Ok(Json(ExpandLinkResponse{
+                id: link.id.value.clone(),
+                destination: link.redirect_url.clone(),
+                resolved_destination: state.link_manager_service.resolve_destination(&link, &redirect_ctx),
+                label: link.label.clone(),
+                created_at: link.created_at,
+            })),
+        Err(LinkManagerError::LinkNotFound(_) | LinkManagerError::NotYetActive(_)) => 
+            Err(StatusCode::NOT_FOUND),
+        Err(LinkManagerError::PreviewNotAllowed(_)) => 
+            Err(StatusCode::FORBIDDEN),
+        Err(_) => 
+            Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
 /// Get link views
 #[utoipa::path(
     get, 
@@ -43,7 +282,8 @@ pub async fn view_link_get_handler(
     ),
     tag = "short-link",
     responses(
-        (status = 200, description = "OK", body = i64),
+        (status = 200, description = "OK", body = i64, content_type = "application/json"),
+        (status = 401, description = "Unauthorized"),
         (status = 404, description = "Not Found"),
         (status = 500, description = "Internal Server Error"),)
 )]
@@ -63,37 +303,356 @@ pub async fn get_link_views_get_handler(
     }
 }
 
+/// Get link views by country
+#[utoipa::path(
+    get, 
+    path = "/get-views/{linkId}/countries", 
+    params(
+        ("linkId" = String, Path, description = "ID of the link")
+    ),
+    tag = "short-link",
+    responses(
+        (status = 200, description = "OK", body = Vec<CountryViews>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),)
+)]
+pub async fn get_link_country_views_get_handler(
+    State(state): State<AppState>,
+    Path(link_id): Path<String>,
+) -> Result<Json<Vec<CountryViews>>, StatusCode> {
+    match state.link_manager_service.get_link_country_views(&LinkId::from_string(link_id)).await{
+        Ok(views) => 
+            Ok(Json(views)),
+        Err(LinkManagerError::LinkNotFound(_)) => 
+            Err(
+                StatusCode::NOT_FOUND,
+            ), 
+        Err(_) => 
+            Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Get link views by A/B variant
+#[utoipa::path(
+    get, 
+    path = "/get-views/{linkId}/variants", 
+    params(
+        ("linkId" = String, Path, description = "ID of the link")
+    ),
+    tag = "short-link",
+    responses(
+        (status = 200, description = "OK", body = Vec<VariantViews>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),)
+)]
+pub async fn get_link_variant_views_get_handler(
+    State(state): State<AppState>,
+    Path(link_id): Path<String>,
+) -> Result<Json<Vec<VariantViews>>, StatusCode> {
+    match state.link_manager_service.get_link_variant_views(&LinkId::from_string(link_id)).await{
+        Ok(views) => 
+            Ok(Json(views)),
+        Err(LinkManagerError::LinkNotFound(_)) => 
+            Err(
+                StatusCode::NOT_FOUND,
+            ), 
+        Err(_) => 
+            Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize, ToSchema)]
 pub struct CreateLinkRequest{
     redirected_url: String,
-    label: String
+    /// Filled in from the destination page title when left out
+    #[serde(default)]
+    label: Option<String>,
+    /// Return an existing link of the user with the same normalized destination, label,
+    /// options and start, unless it is used up or in the trash
+    #[serde(default)]
+    reuse_existing: bool,
+    /// UTM params merged into the destination on redirect
+    #[serde(default)]
+    utm: UtmParams,
+    /// Name of a configured UTM campaign template
+    utm_template: Option<String>,
+    /// Forward the incoming query string to the destination
+    #[serde(default)]
+    forward_query: bool,
+    /// Forward path segments after the link id to the destination
+    #[serde(default)]
+    forward_path: bool,
+    /// Redirect status, the configured default when not set
+    redirect_type: Option<RedirectType>,
+    /// Burn after the first view
+    #[serde(default)]
+    one_time: bool,
+    /// User-Agent based destinations
+    #[serde(default)]
+    routing: RoutingRules,
+    /// Country and region based destinations
+    #[serde(default)]
+    geo: Vec<GeoRule>,
+    /// Social card for link preview crawlers
+    #[serde(default)]
+    og: OpenGraph,
+    /// Weighted A/B destinations
+    #[serde(default)]
+    variants: Vec<Variant>,
+    /// `Accept-Language` based destinations
+    #[serde(default)]
+    languages: LanguageRules,
+    /// Time-window destinations
+    #[serde(default)]
+    schedule: Schedule,
+    /// The link redirects only from this moment on
+    active_from: Option<chrono::DateTime<chrono::Utc>>,
+    /// Used in place of the destination while it is reported broken
+    fallback_url: Option<String>,
+}
+
+impl From<CreateLinkRequest> for NewLink {
+    fn from(payload: CreateLinkRequest) -> Self {
+        let options = LinkOptions {
+            utm: payload.utm,
+            utm_template: payload.utm_template,
+            forward_query: payload.forward_query,
+            forward_path: payload.forward_path,
+            one_time: payload.one_time,
+            redirect_type: payload.redirect_type,
+            routing: payload.routing,
+            geo: payload.geo,
+            og: payload.og,
+            variants: payload.variants,
+            languages: payload.languages,
+            schedule: payload.schedule,
+            fallback_url: payload.fallback_url,
+        };
+
+        NewLink::new(payload.redirected_url, payload.label.unwrap_or_default())
+            .with_active_from(payload.active_from)
+            .with_options(options)
+    }
+}
+
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+fn idempotency_key_from_headers(headers: &HeaderMap, payload: &CreateLinkRequest) -> Result<Option<IdempotencyKey>, StatusCode> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+
+    let key = value.to_str().map_err(|_| StatusCode::BAD_REQUEST)?.trim();
+    if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let body = serde_json::to_vec(payload).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let fingerprint = hex::encode(Sha256::digest(body));
+
+    Ok(Some(IdempotencyKey::new(key.to_string(), fingerprint)))
 }
 
 /// Create short link
+///
+/// Retries carrying the same `Idempotency-Key` header replay the originally created link id.
 #[utoipa::path(
     post, 
     path = "/create-link", 
     tag = "short-link",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Key to safely retry the request")
+    ),
     request_body = CreateLinkRequest,
     responses(
         (status = 200, description = "OK", body = LinkId),
+        (status = 400, description = "Bad Request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "Idempotency key reused with a different request"),
         (status = 500, description = "Internal Server Error"),)
 )]
 
 pub async fn create_link_post_handler(
     State(state): State<AppState>,
     Extension(middleware_user): Extension<MiddlewareUserResponse>,
+    headers: HeaderMap,
     Json(payload): Json<CreateLinkRequest>, 
 ) -> Result<Json<LinkId>, StatusCode> {
-    match state.link_manager_service.create_link(middleware_user.user_id, payload. redirected_url,  payload.label).await{
+    let idempotency_key = idempotency_key_from_headers(&headers, &payload)?;
+
+    let reuse_existing = payload.reuse_existing;
+
+    match state.link_manager_service.create_link(middleware_user.user_id, NewLink::from(payload), reuse_existing, idempotency_key).await{
         Ok(link_id) => 
             Ok(Json(link_id)),
+        Err(LinkManagerError::IdempotencyKeyReused(_)) => 
+            Err(StatusCode::UNPROCESSABLE_ENTITY),
+        Err(LinkManagerError::UnknownUtmTemplate(_) | LinkManagerError::InvalidOptions(_)) => 
+            Err(StatusCode::BAD_REQUEST),
         Err(_) => 
             Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
 
+#[derive(Debug, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct CreateLinksRequest{
+    links: Vec<CreateLinkRequest>,
+}
+
+#[derive(Debug, serde::Serialize, ToSchema)]
+pub struct CreateLinksResponse{
+    link_ids: Vec<LinkId>,
+}
+
+fn bulk_error_response(error: LinkManagerError) -> (StatusCode, Json<Vec<LinkRowError>>) {
+    match error {
+        LinkManagerError::InvalidRows(row_errors) => 
+            (StatusCode::BAD_REQUEST, Json(row_errors)),
+        LinkManagerError::TooManyLinks(max) => 
+            (StatusCode::PAYLOAD_TOO_LARGE, Json(vec![LinkRowError::new(0, format!("too many links, max: {max}"))])),
+        _ => 
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(vec![])),
+    }
+}
+
+/// Create short links in bulk
+#[utoipa::path(
+    post, 
+    path = "/create-links", 
+    tag = "short-link",
+    request_body = CreateLinksRequest,
+    responses(
+        (status = 200, description = "OK", body = CreateLinksResponse),
+        (status = 400, description = "Bad Request", body = Vec<LinkRowError>),
+        (status = 401, description = "Unauthorized"),
+        (status = 413, description = "Payload Too Large", body = Vec<LinkRowError>),
+        (status = 500, description = "Internal Server Error"),)
+)]
+pub async fn create_links_post_handler(
+    State(state): State<AppState>,
+    Extension(middleware_user): Extension<MiddlewareUserResponse>,
+    Json(payload): Json<CreateLinksRequest>, 
+) -> Result<Json<CreateLinksResponse>, (StatusCode, Json<Vec<LinkRowError>>)> {
+    let new_links = payload.links.into_iter()
+        .map(|link| {
+            let reuse_existing = link.reuse_existing;
+            (NewLink::from(link), reuse_existing)
+        })
+        .collect();
+
+    match state.link_manager_service.create_links(middleware_user.user_id, new_links).await{
+        Ok(link_ids) => 
+            Ok(Json(CreateLinksResponse { link_ids })),
+        Err(error) => 
+            Err(bulk_error_response(error)),
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ImportLinksQuery{
+    #[serde(default)]
+    reuse_existing: bool,
+}
+
+/// Import short links from CSV
+///
+/// Accepts our own export format as well as Bitly exports (`long_url`, `title`).
+#[utoipa::path(
+    post, 
+    path = "/import-links", 
+    params(
+        ("reuse_existing" = Option<bool>, Query, description = "Return existing links instead of creating duplicates, as in /create-link")
+    ),
+    tag = "short-link",
+    request_body(content = String, content_type = "text/csv"),
+    responses(
+        (status = 200, description = "OK", body = CreateLinksResponse),
+        (status = 400, description = "Bad Request", body = Vec<LinkRowError>),
+        (status = 401, description = "Unauthorized"),
+        (status = 413, description = "Payload Too Large", body = Vec<LinkRowError>),
+        (status = 500, description = "Internal Server Error"),)
+)]
+pub async fn import_links_post_handler(
+    State(state): State<AppState>,
+    Extension(middleware_user): Extension<MiddlewareUserResponse>,
+    Query(query): Query<ImportLinksQuery>,
+    body: String, 
+) -> Result<Json<CreateLinksResponse>, (StatusCode, Json<Vec<LinkRowError>>)> {
+    let new_links = parse_links_csv(&body).map_err(|row_errors| (StatusCode::BAD_REQUEST, Json(row_errors)))?
+        .into_iter()
+        .map(|new_link| (new_link, query.reuse_existing))
+        .collect();
+
+    match state.link_manager_service.create_links(middleware_user.user_id, new_links).await{
+        Ok(link_ids) => 
+            Ok(Json(CreateLinksResponse { link_ids })),
+        Err(error) => 
+            Err(bulk_error_response(error)),
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat{
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ExportLinksQuery{
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+/// Export all user links with their stats
+#[utoipa::path(
+    get, 
+    path = "/export-links", 
+    params(
+        ("format" = Option<ExportFormat>, Query, description = "Export format, json by default")
+    ),
+    tag = "short-link",
+    responses(
+        (status = 200, description = "OK", content(
+            (Vec<LinkExportRow> = "application/json"),
+            (String = "text/csv"),
+        )),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal Server Error"),)
+)]
+pub async fn export_links_get_handler(
+    State(state): State<AppState>,
+    Extension(middleware_user): Extension<MiddlewareUserResponse>,
+    Query(query): Query<ExportLinksQuery>,
+) -> Result<Response, StatusCode> {
+    let links = state.link_manager_service.get_user_links(middleware_user.user_id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    match query.format {
+        ExportFormat::Json => {
+            let rows: Vec<LinkExportRow> = links.into_iter().map(LinkExportRow::from).collect();
+            Ok(Json(rows).into_response())
+        }
+        ExportFormat::Csv => {
+            let body = write_links_csv(links).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            Ok((
+                [
+                    (header::CONTENT_TYPE, "text/csv"),
+                    (header::CONTENT_DISPOSITION, "attachment; filename=\"links.csv\""),
+                ],
+                body,
+            ).into_response())
+        }
+    }
+}
+
 /// Delete link
+///
+/// Moves the link to the trash. It stops redirecting immediately and can be restored until
+/// it is purged after the configured retention period.
 #[utoipa::path(
     delete, 
     path = "/delete-link/{linkId}", 
@@ -102,7 +661,10 @@ pub async fn create_link_post_handler(
     ),
     tag = "short-link",
     responses(
-        (status = 200, description = "OK", body = bool),
+        (status = 200, description = "OK", body = bool, content_type = "application/json"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Not Found"),
         (status = 500, description = "Internal Server Error"),)
 )]
 pub async fn delete_link_delete_handler(
@@ -113,7 +675,178 @@ pub async fn delete_link_delete_handler(
     match state.link_manager_service.delete_link(LinkId::from_string(link_id), middleware_user.user_id).await{
         Ok(_) => 
             Ok(Json(true)),
+        Err(LinkManagerError::LinkNotFound(_)) => 
+            Err(StatusCode::NOT_FOUND),
+        Err(LinkManagerError::LinkNotOwnedByUser(_, _)) => 
+            Err(StatusCode::FORBIDDEN),
+        Err(_) => 
+            Err(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+
+/// Restore link from the trash
+#[utoipa::path(
+    post, 
+    path = "/restore-link/{linkId}", 
+    params(
+        ("linkId" = String, Path, description = "ID of the link")
+    ),
+    tag = "short-link",
+    responses(
+        (status = 200, description = "OK", body = bool, content_type = "application/json"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Not Found in the trash"),
+        (status = 500, description = "Internal Server Error"),)
+)]
+pub async fn restore_link_post_handler(
+    State(state): State<AppState>,
+    Extension(middleware_user): Extension<MiddlewareUserResponse>,
+    Path(link_id): Path<String>,
+) -> Result<Json<bool>, StatusCode> {
+    match state.link_manager_service.restore_link(&LinkId::from_string(link_id), middleware_user.user_id).await{
+        Ok(_) => 
+            Ok(Json(true)),
+        Err(LinkManagerError::LinkNotFound(_)) => 
+            Err(StatusCode::NOT_FOUND),
+        Err(LinkManagerError::LinkNotOwnedByUser(_, _)) => 
+            Err(StatusCode::FORBIDDEN),
         Err(_) => 
             Err(StatusCode::INTERNAL_SERVER_ERROR),
         }
     }
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct TrashedLinkResponse{
+    id: String,
+    redirect_url: String,
+    label: String,
+    deleted_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// List links in the trash
+#[utoipa::path(
+    get, 
+    path = "/trash-links", 
+    tag = "short-link",
+    responses(
+        (status = 200, description = "OK", body = Vec<TrashedLinkResponse>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal Server Error"),)
+)]
+pub async fn trash_links_get_handler(
+    State(state): State<AppState>,
+    Extension(middleware_user): Extension<MiddlewareUserResponse>,
+) -> Result<Json<Vec<TrashedLinkResponse>>, StatusCode> {
+    match state.link_manager_service.get_trashed_links(middleware_user.user_id).await{
+        Ok(links) => 
+            Ok(Json(links.into_iter().filter_map(|link| Some(TrashedLinkResponse{
+                deleted_at: link.deleted_at?,
+                id: link.id.value.clone(),
+                redirect_url: link.redirect_url.clone(),
+                label: link.label.clone(),
+            })).collect())),
+        Err(_) => 
+            Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct HealthReportQuery{
+    #[serde(default)]
+    broken_only: bool,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct LinkHealthResponse{
+    id: String,
+    redirect_url: String,
+    label: String,
+    /// Set once the destination failed enough checks in a row
+    broken_since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Consecutive failed checks
+    failures: i32,
+    last_check: Option<HealthCheck>,
+}
+
+/// Health of the user's link destinations, broken links first
+#[utoipa::path(
+    get, 
+    path = "/health-report", 
+    params(
+        ("broken_only" = Option<bool>, Query, description = "Only list broken links")
+    ),
+    tag = "short-link",
+    responses(
+        (status = 200, description = "OK", body = Vec<LinkHealthResponse>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal Server Error"),)
+)]
+pub async fn health_report_get_handler(
+    State(state): State<AppState>,
+    Extension(middleware_user): Extension<MiddlewareUserResponse>,
+    Query(query): Query<HealthReportQuery>,
+) -> Result<Json<Vec<LinkHealthResponse>>, StatusCode> {
+    match state.link_manager_service.get_health_report(middleware_user.user_id).await{
+        Ok(report) => 
+            Ok(Json(report.into_iter()
+                .filter(|health| !query.broken_only || health.state.broken_since.is_some())
+                .map(|health| LinkHealthResponse{
+                    id: health.link_id.value.clone(),
+                    redirect_url: health.redirect_url,
+                    label: health.label,
+                    broken_since: health.state.broken_since,
+                    failures: health.state.failures,
+                    last_check: health.last_check,
+                }).collect())),
+        Err(_) => 
+            Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Health check history of a link, latest first
+#[utoipa::path(
+    get, 
+    path = "/link-health/{linkId}", 
+    params(
+        ("linkId" = String, Path, description = "ID of the link")
+    ),
+    tag = "short-link",
+    responses(
+        (status = 200, description = "OK", body = Vec<HealthCheck>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),)
+)]
+pub async fn link_health_get_handler(
+    State(state): State<AppState>,
+    Extension(middleware_user): Extension<MiddlewareUserResponse>,
+    Path(link_id): Path<String>,
+) -> Result<Json<Vec<HealthCheck>>, StatusCode> {
+    match state.link_manager_service.get_link_health_checks(&LinkId::from_string(link_id), middleware_user.user_id).await{
+        Ok(checks) => 
+            Ok(Json(checks)),
+        Err(LinkManagerError::LinkNotFound(_)) => 
+            Err(StatusCode::NOT_FOUND),
+        Err(LinkManagerError::LinkNotOwnedByUser(_, _)) => 
+            Err(StatusCode::FORBIDDEN),
+        Err(_) => 
+            Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Link cache hit and miss counters per tier
+#[utoipa::path(
+    get, 
+    path = "/cache-stats", 
+    tag = "short-link",
+    responses(
+        (status = 200, description = "OK", body = CacheStats),
+        (status = 401, description = "Unauthorized"),)
+)]
+pub async fn cache_stats_get_handler(
+    State(state): State<AppState>,
+) -> Json<CacheStats> {
+    Json(state.link_manager_service.cache_stats())
+}
@@ -1 +1,3 @@
-pub mod http;
\ No newline at end of file
+pub mod csv;
+pub mod html;
+pub mod http;
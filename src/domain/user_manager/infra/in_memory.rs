@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+use solar::trx_factory::TrxContext;
+
+use crate::domain::auth::entity::user::User;
+use crate::domain::auth::infra::in_memory::InMemoryUsers;
+use crate::domain::user_manager::service::{PersistenceError, PersistenceRepo};
+
+pub struct InMemoryUserManagerRepo {
+    users: Arc<InMemoryUsers>,
+}
+
+impl InMemoryUserManagerRepo {
+    pub fn new(users: Arc<InMemoryUsers>) -> Self {
+        Self { users }
+    }
+}
+
+#[async_trait::async_trait]
+impl PersistenceRepo for InMemoryUserManagerRepo {
+    async fn save_user(&self, user: User, _ctx: TrxContext) -> Result<i32, PersistenceError> {
+        Ok(self.users.save(user))
+    }
+
+    async fn get_user_by_id(
+        &self,
+        user_id: i32,
+        _ctx: TrxContext,
+    ) -> Result<Option<User>, PersistenceError> {
+        Ok(self.users.find(|u| u.id == user_id))
+    }
+}
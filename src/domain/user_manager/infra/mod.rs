@@ -1 +1,4 @@
-pub mod persistence;
\ No newline at end of file
+#[cfg(test)]
+pub mod in_memory;
+pub mod persistence;
+pub mod sqlite;
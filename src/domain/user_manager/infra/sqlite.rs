@@ -0,0 +1,72 @@
+use chrono::Utc;
+use eyre::Context;
+use solar::trx_factory::TrxContext;
+
+use crate::domain::auth::entity::user::User;
+use crate::domain::user_manager::service::{PersistenceError, PersistenceRepo};
+use crate::tools::sqlite_trx::SqliteTrxFactory;
+
+pub struct UserManagerSqliteRepo {
+    trx_factory: SqliteTrxFactory,
+}
+
+impl UserManagerSqliteRepo {
+    pub fn new(trx_factory: SqliteTrxFactory) -> Self {
+        Self { trx_factory }
+    }
+}
+
+#[async_trait::async_trait]
+impl PersistenceRepo for UserManagerSqliteRepo {
+    async fn save_user(&self, user: User, ctx: TrxContext) -> Result<i32, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let (user_id,) = sqlx::query_as::<_, (i32,)>(
+            r#"
+            INSERT INTO users (name, email, password, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $4)
+            ON CONFLICT (email) DO UPDATE
+            SET name = excluded.name,
+                updated_at = excluded.updated_at
+            RETURNING id
+            "#,
+        )
+        .bind(user.name)
+        .bind(user.email)
+        .bind(user.password)
+        .bind(Utc::now())
+        .fetch_one(&mut **trx)
+        .await
+        .context("failed to save user")?;
+
+        Ok(user_id)
+    }
+
+    async fn get_user_by_id(
+        &self,
+        user_id: i32,
+        ctx: TrxContext,
+    ) -> Result<Option<User>, PersistenceError> {
+        let (trx, _) = self.trx_factory.extract_or_create_trx(ctx).await?;
+        let mut trx = trx.lock().await;
+        let Some(trx) = trx.as_mut() else {
+            return Err(PersistenceError::InternalError(eyre::eyre!(
+                "failed to get sqlx transaction"
+            )));
+        };
+
+        let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&mut **trx)
+            .await
+            .context("failed to find user by id")?;
+
+        Ok(user)
+    }
+}
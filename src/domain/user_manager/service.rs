@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use chrono::Utc;
 use solar::trx_factory::{TrxContext, TrxFactory, TrxFactoryError};
 
@@ -24,6 +26,22 @@ pub trait PersistenceRepo: Send + Sync {
     ) -> Result<Option<User>, PersistenceError>;
 }
 
+/// Lets the repo be chosen at runtime, see `DatabaseConfig`.
+#[async_trait::async_trait]
+impl<T: PersistenceRepo + ?Sized> PersistenceRepo for Arc<T> {
+    async fn save_user(&self, user: User, ctx: TrxContext) -> Result<i32, PersistenceError> {
+        (**self).save_user(user, ctx).await
+    }
+
+    async fn get_user_by_id(
+        &self,
+        user_id: i32,
+        ctx: TrxContext,
+    ) -> Result<Option<User>, PersistenceError> {
+        (**self).get_user_by_id(user_id, ctx).await
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum UserManagerError {
     #[error("trx factory error: {0}")]
@@ -82,3 +100,47 @@ where
         Ok(UserNoPassword::new(user.id, user.name, user.email))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        domain::{
+            auth::infra::in_memory::InMemoryUsers,
+            user_manager::infra::in_memory::InMemoryUserManagerRepo,
+        },
+        tools::in_memory_trx::InMemoryTrxFactory,
+    };
+
+    fn user_manager_service() -> UserManagerService<InMemoryUserManagerRepo, InMemoryTrxFactory> {
+        let users = Arc::new(InMemoryUsers::default());
+        users.save(User::new(
+            "alice".to_string(),
+            "alice@example.com".to_string(),
+            "secret".to_string(),
+        ));
+        UserManagerService::new(InMemoryUserManagerRepo::new(users), InMemoryTrxFactory)
+    }
+
+    #[tokio::test]
+    async fn change_name_updates_user_info() {
+        let service = user_manager_service();
+
+        service.change_name(1, "alicia".to_string()).await.unwrap();
+        let user = service.get_user_info(1).await.unwrap();
+
+        assert_eq!(user.name, "alicia");
+        assert_eq!(user.email, "alice@example.com");
+    }
+
+    #[tokio::test]
+    async fn unknown_user_is_not_found() {
+        let service = user_manager_service();
+
+        let result = service.get_user_info(42).await;
+
+        assert!(matches!(result, Err(UserManagerError::UserNotFound(42))));
+    }
+}
@@ -15,6 +15,7 @@ use crate::{domain::user_manager::{entity::user_no_password::UserNoPassword, ser
     tag = "user",
     responses(
         (status = 200, description = "OK", body = UserNoPassword),
+        (status = 401, description = "Unauthorized"),
         (status = 404, description = "Not Found"),
         (status = 500, description = "Internal Server Error"),)
 )]
@@ -42,11 +43,12 @@ pub struct ChangeNameRequest{
 /// Change user name
 #[utoipa::path(
     post, 
-    path = "/user/change-name", 
+    path = "/change-name", 
     tag = "short-link",
     request_body = ChangeNameRequest,
     responses(
         (status = 200, description = "OK"),
+        (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal Server Error"),)
 )]
 
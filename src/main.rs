@@ -5,23 +5,41 @@ pub mod router;
 pub mod tools;
 pub mod transport;
 
-use container::build_container;
+#[cfg(test)]
+mod tests;
+
+use container::{
+    AppAuthService, AppLinkManagerService, AppUserManagerService, Container, build_container,
+};
 use dotenv::dotenv;
 use router::build_router;
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 
-use domain::{
-    auth::{infra::persistence::AuthPersistenceRepo, service::AuthService},
-    link_manager::{infra::persistence::LinkManagerPersistenceRepo, service::LinkManagerService},
-    user_manager::{infra::persistence::UserManagerPersistenceRepo, service::UserManagerService},
+use tools::{
+    client_ip::ClientIpResolver, geoip::GeoIpResolver, redis_connection::RedisConnection,
 };
-use solar::trx_factory::SqlxTrxFactory;
 
 #[derive(Clone)]
 pub struct AppState {
-    auth_service: Arc<AuthService<AuthPersistenceRepo, SqlxTrxFactory>>,
-    link_manager_service: Arc<LinkManagerService<LinkManagerPersistenceRepo, SqlxTrxFactory>>,
-    user_manager_service: Arc<UserManagerService<UserManagerPersistenceRepo, SqlxTrxFactory>>,
+    auth_service: Arc<AppAuthService>,
+    link_manager_service: Arc<AppLinkManagerService>,
+    user_manager_service: Arc<AppUserManagerService>,
+    client_ip_resolver: Arc<ClientIpResolver>,
+    geo_ip_resolver: Arc<GeoIpResolver>,
+    redis_connection: Option<Arc<RedisConnection>>,
+}
+
+impl AppState {
+    fn new(container: &Container) -> Self {
+        Self {
+            auth_service: container.auth_service.clone(),
+            link_manager_service: container.link_manager_service.clone(),
+            user_manager_service: container.user_manager_service.clone(),
+            client_ip_resolver: container.client_ip_resolver.clone(),
+            geo_ip_resolver: container.geo_ip_resolver.clone(),
+            redis_connection: container.redis_connection.clone(),
+        }
+    }
 }
 
 #[tokio::main]
@@ -30,13 +48,7 @@ async fn main() {
 
     let container = build_container().await;
 
-    let app_state = AppState {
-        auth_service: container.auth_service.clone(),
-        link_manager_service: container.link_manager_service.clone(),
-        user_manager_service: container.user_manager_service.clone(),
-    };
-
-    let router = build_router(app_state);
+    let router = build_router(AppState::new(&container));
 
     let addr = container.server_address.clone();
 
@@ -46,5 +58,10 @@ async fn main() {
 
     println!("Server running on: {addr:?}");
 
-    axum::serve(listener, router).await.unwrap();
+    axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
@@ -3,16 +3,21 @@ use crate::{
     domain::{
         auth::transport::http::{login_post_handler, register_post_handler},
         link_manager::transport::http::{
-            create_link_post_handler, get_link_views_get_handler, view_link_get_handler,
+            cache_stats_get_handler, create_link_post_handler, create_links_post_handler,
+            delete_link_delete_handler, expand_link_get_handler, export_links_get_handler,
+            get_link_country_views_get_handler, get_link_variant_views_get_handler,
+            get_link_views_get_handler, health_report_get_handler, import_links_post_handler,
+            link_health_get_handler, preview_link_get_handler, restore_link_post_handler,
+            trash_links_get_handler, view_link_get_handler,
         },
         user_manager::transport::http::{change_name_post_handler, get_user_info_get_handler},
     },
-    transport::http::auth::user_middleware,
+    transport::http::{auth::user_middleware, health::health_get_handler},
 };
 use axum::{
     Router,
     middleware::from_fn_with_state,
-    routing::{get, post},
+    routing::{delete, get, post},
 };
 
 use utoipa::OpenApi;
@@ -25,20 +30,34 @@ use utoipa_swagger_ui::SwaggerUi;
         crate::domain::auth::transport::http::register_post_handler,
 
         crate::domain::link_manager::transport::http::view_link_get_handler,
+        crate::domain::link_manager::transport::http::preview_link_get_handler,
+        crate::domain::link_manager::transport::http::expand_link_get_handler,
         crate::domain::link_manager::transport::http::get_link_views_get_handler,
+        crate::domain::link_manager::transport::http::get_link_country_views_get_handler,
+        crate::domain::link_manager::transport::http::get_link_variant_views_get_handler,
         crate::domain::link_manager::transport::http::create_link_post_handler,
+        crate::domain::link_manager::transport::http::create_links_post_handler,
+        crate::domain::link_manager::transport::http::import_links_post_handler,
+        crate::domain::link_manager::transport::http::export_links_get_handler,
         crate::domain::link_manager::transport::http::delete_link_delete_handler,
+        crate::domain::link_manager::transport::http::restore_link_post_handler,
+        crate::domain::link_manager::transport::http::trash_links_get_handler,
+        crate::domain::link_manager::transport::http::health_report_get_handler,
+        crate::domain::link_manager::transport::http::link_health_get_handler,
+        crate::domain::link_manager::transport::http::cache_stats_get_handler,
 
 
         crate::domain::user_manager::transport::http::change_name_post_handler,
         crate::domain::user_manager::transport::http::get_user_info_get_handler,
+
+        crate::transport::http::health::health_get_handler,
     ),
         servers(
         (url = "http://localhost:3000", description = "Local server")
     ),
 
     tags((name = "short-link", description = "API Documentation")))]
-struct ApiDoc {}
+pub struct ApiDoc {}
 
 pub fn build_router(app_state: AppState) -> Router {
     Router::new()
@@ -52,8 +71,61 @@ pub fn build_router(app_state: AppState) -> Router {
                 .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
         )
         .route(
-            "/view/{link_id}",
-            get(view_link_get_handler)
+            "/create-links",
+            post(create_links_post_handler)
+                .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
+        )
+        .route(
+            "/import-links",
+            post(import_links_post_handler)
+                .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
+        )
+        .route(
+            "/export-links",
+            get(export_links_get_handler)
+                .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
+        )
+        .route(
+            "/delete-link/{link_id}",
+            delete(delete_link_delete_handler)
+                .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
+        )
+        .route(
+            "/restore-link/{link_id}",
+            post(restore_link_post_handler)
+                .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
+        )
+        .route(
+            "/trash-links",
+            get(trash_links_get_handler)
+                .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
+        )
+        .route(
+            "/health-report",
+            get(health_report_get_handler)
+                .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
+        )
+        .route(
+            "/link-health/{link_id}",
+            get(link_health_get_handler)
+                .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
+        )
+        .route(
+            "/cache-stats",
+            get(cache_stats_get_handler)
+                .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
+        )
+        // public, visitors and link preview crawlers follow short links without our auth
+        .route("/view/{link_id}", get(view_link_get_handler))
+        .route("/view/{link_id}/{*rest}", get(view_link_get_handler))
+        .route(
+            "/preview/{link_id}",
+            get(preview_link_get_handler)
+                .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
+        )
+        .route(
+            "/expand/{link_id}",
+            get(expand_link_get_handler)
                 .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
         )
         .route(
@@ -61,6 +133,16 @@ pub fn build_router(app_state: AppState) -> Router {
             get(get_link_views_get_handler)
                 .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
         )
+        .route(
+            "/get-views/{link-id}/countries",
+            get(get_link_country_views_get_handler)
+                .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
+        )
+        .route(
+            "/get-views/{link-id}/variants",
+            get(get_link_variant_views_get_handler)
+                .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
+        )
         // user manager
         .route(
             "/change-name",
@@ -72,6 +154,7 @@ pub fn build_router(app_state: AppState) -> Router {
             get(get_user_info_get_handler)
                 .route_layer(from_fn_with_state(app_state.clone(), user_middleware)),
         )
+        .route("/health", get(health_get_handler))
         .merge(SwaggerUi::new("/swagger-ui").url("/api-doc/openapi.json", ApiDoc::openapi()))
         .with_state(app_state)
 }
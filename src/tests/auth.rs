@@ -0,0 +1,71 @@
+use axum::http::{StatusCode, header};
+use sqlx::PgPool;
+
+use super::harness::{TestApp, TestUser};
+
+#[sqlx::test]
+async fn login_returns_jwt_and_token_cookie(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+
+    let user = app.user("alice").await;
+
+    let response = app.get("/trash-links").cookie(&user).send().await;
+    assert_eq!(response.status, StatusCode::OK);
+    let response = app.get("/trash-links").bearer(&user).send().await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn register_rejects_taken_email(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    app.user("alice").await;
+
+    let response = app.register("other alice", "alice@example.com").await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn login_rejects_wrong_password(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    app.user("alice").await;
+
+    let response = app.login("alice@example.com", "wrong").await;
+
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(response.cookie("token"), None);
+}
+
+#[sqlx::test]
+async fn login_rejects_unknown_email(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+
+    let response = app.login("nobody@example.com", "secret").await;
+
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn protected_routes_require_a_valid_token(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let forged = TestUser {
+        id: 1,
+        jwt: "not-a-jwt".to_string(),
+    };
+
+    let response = app.get("/trash-links").send().await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app.get("/trash-links").bearer(&forged).send().await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app.get("/trash-links").cookie(&forged).send().await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .get("/trash-links")
+        .header(header::AUTHORIZATION, "Basic YWxpY2U6c2VjcmV0")
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
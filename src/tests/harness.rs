@@ -0,0 +1,323 @@
+use std::{
+    env,
+    net::SocketAddr,
+    sync::{Arc, Once},
+    time::Duration,
+};
+
+use axum::{
+    Router,
+    body::{Body, Bytes, to_bytes},
+    extract::connect_info::MockConnectInfo,
+    http::{HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode, header},
+};
+use serde_json::{Value, json};
+use solar::trx_factory::SqlxTrxFactory;
+use sqlx::PgPool;
+use tower::ServiceExt;
+use utoipa::OpenApi;
+
+use super::openapi::assert_conforms;
+use crate::{
+    AppState,
+    config::{CacheConfig, LinksConfig, RedisConfig},
+    domain::{
+        auth::{self, infra::persistence::AuthPersistenceRepo, service::AuthService},
+        link_manager::{
+            self,
+            infra::{
+                in_memory::InMemoryLinkCache, persistence::LinkManagerPersistenceRepo,
+                read_replicas::ReadReplicas,
+            },
+            service::LinkManagerService,
+        },
+        user_manager::{
+            self, infra::persistence::UserManagerPersistenceRepo, service::UserManagerService,
+        },
+    },
+    router::{ApiDoc, build_router},
+    tools::{
+        any_trx::AnyTrxFactory, client_ip::ClientIpResolver, geoip::GeoIpResolver,
+        redis_connection::RedisConnection,
+    },
+};
+
+const SECRET_JWT: &str = "test-secret";
+const LINK_CACHE_EXPIRATION_SEC: u64 = 3600;
+const IDEMPOTENCY_KEY_EXPIRATION_SEC: u64 = 86400;
+/// Nothing listens on port 1, the connection is refused right away.
+const UNREACHABLE_REDIS_URL: &str = "redis://127.0.0.1:1/";
+const PASSWORD: &str = "secret";
+
+static SET_SECRET_JWT: Once = Once::new();
+
+fn set_secret_jwt() {
+    SET_SECRET_JWT.call_once(|| {
+        // SAFETY: runs once, before any test reads the environment, and every test sets the
+        // same value.
+        unsafe { env::set_var("SECRET_JWT", SECRET_JWT) };
+    });
+}
+
+/// The full router over a fresh database, with the peer address mocked.
+pub struct TestApp {
+    router: Router,
+    openapi: Value,
+}
+
+/// A registered and logged-in user.
+#[derive(Debug, Clone)]
+pub struct TestUser {
+    pub id: i32,
+    pub jwt: String,
+}
+
+impl TestApp {
+    pub async fn new(pool: PgPool) -> Self {
+        let trx_factory = SqlxTrxFactory::new(pool);
+        let link_manager_repo = LinkManagerPersistenceRepo::new(trx_factory.clone());
+        Self::with_link_manager_repo(trx_factory, link_manager_repo).await
+    }
+
+    /// Link lookups and stats outside a transaction are read from `replica`.
+    pub async fn with_read_replica(pool: PgPool, replica: PgPool, lag_guard: Duration) -> Self {
+        let trx_factory = SqlxTrxFactory::new(pool);
+        let link_manager_repo = LinkManagerPersistenceRepo::new(trx_factory.clone())
+            .with_read_replicas(ReadReplicas::new(vec![replica], lag_guard));
+        Self::with_link_manager_repo(trx_factory, link_manager_repo).await
+    }
+
+    async fn with_link_manager_repo(
+        trx_factory: SqlxTrxFactory,
+        link_manager_repo: LinkManagerPersistenceRepo,
+    ) -> Self {
+        let auth_repo: Arc<dyn auth::service::PersistenceRepo> =
+            Arc::new(AuthPersistenceRepo::new(trx_factory.clone()));
+        let link_manager_repo: Arc<dyn link_manager::service::PersistenceRepo> =
+            Arc::new(link_manager_repo);
+        let user_manager_repo: Arc<dyn user_manager::service::PersistenceRepo> =
+            Arc::new(UserManagerPersistenceRepo::new(trx_factory.clone()));
+        let trx_factory = AnyTrxFactory::Postgres(trx_factory);
+
+        let auth_service = AuthService::new(auth_repo, trx_factory.clone());
+        let link_manager_service = LinkManagerService::new(
+            link_manager_repo,
+            trx_factory.clone(),
+            Arc::new(InMemoryLinkCache::new()),
+            LINK_CACHE_EXPIRATION_SEC,
+            IDEMPOTENCY_KEY_EXPIRATION_SEC,
+            LinksConfig::default(),
+        )
+        .with_cache_config(&CacheConfig::default());
+        let user_manager_service = UserManagerService::new(user_manager_repo, trx_factory);
+
+        let redis_config = RedisConfig {
+            url: UNREACHABLE_REDIS_URL.to_string(),
+            connect_timeout_ms: 100,
+            response_timeout_ms: 100,
+            failure_threshold: 1,
+            open_sec: 1,
+            reconnect_interval_sec: 1,
+        };
+        let redis_client = redis::Client::open(UNREACHABLE_REDIS_URL).unwrap();
+        let redis_connection = RedisConnection::connect(redis_client, &redis_config).await;
+
+        let app_state = AppState {
+            auth_service: Arc::new(auth_service),
+            link_manager_service: Arc::new(link_manager_service),
+            user_manager_service: Arc::new(user_manager_service),
+            client_ip_resolver: Arc::new(ClientIpResolver::new(vec![])),
+            geo_ip_resolver: Arc::new(GeoIpResolver::disabled()),
+            redis_connection: Some(Arc::new(redis_connection)),
+        };
+
+        Self::with_state(app_state)
+    }
+
+    /// Over services built elsewhere, e.g. by the container.
+    pub fn with_state(app_state: AppState) -> Self {
+        set_secret_jwt();
+
+        let peer = SocketAddr::from(([203, 0, 113, 7], 40000));
+        let router = build_router(app_state).layer(MockConnectInfo(peer));
+        let openapi = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+        Self { router, openapi }
+    }
+
+    pub fn get(&self, path: &str) -> TestRequest<'_> {
+        TestRequest::new(self, Method::GET, path)
+    }
+
+    pub fn post(&self, path: &str) -> TestRequest<'_> {
+        TestRequest::new(self, Method::POST, path)
+    }
+
+    pub fn delete(&self, path: &str) -> TestRequest<'_> {
+        TestRequest::new(self, Method::DELETE, path)
+    }
+
+    pub async fn register(&self, name: &str, email: &str) -> TestResponse {
+        self.post("/register")
+            .json(json!({ "name": name, "email": email, "password": PASSWORD }))
+            .send()
+            .await
+    }
+
+    pub async fn login(&self, email: &str, password: &str) -> TestResponse {
+        self.post("/login")
+            .json(json!({ "email": email, "password": password }))
+            .send()
+            .await
+    }
+
+    /// Registers `{name}@example.com` and logs in.
+    pub async fn user(&self, name: &str) -> TestUser {
+        let email = format!("{name}@example.com");
+        let registered = self.register(name, &email).await;
+        assert_eq!(registered.status, StatusCode::OK, "{}", registered.text());
+
+        let login = self.login(&email, PASSWORD).await;
+        assert_eq!(login.status, StatusCode::OK, "{}", login.text());
+
+        let body = login.json();
+        let jwt = body["jwt"].as_str().unwrap().to_string();
+        assert_eq!(login.cookie("token").as_deref(), Some(jwt.as_str()));
+
+        TestUser {
+            id: body["user_id"].as_i64().unwrap() as i32,
+            jwt,
+        }
+    }
+
+    /// Creates a link through the API and returns its id.
+    pub async fn create_link(&self, user: &TestUser, body: Value) -> String {
+        let response = self
+            .post("/create-link")
+            .bearer(user)
+            .json(body)
+            .send()
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+        response.json()["value"].as_str().unwrap().to_string()
+    }
+}
+
+pub struct TestRequest<'a> {
+    app: &'a TestApp,
+    method: Method,
+    uri: String,
+    headers: HeaderMap,
+    body: Body,
+    checked: bool,
+}
+
+impl<'a> TestRequest<'a> {
+    fn new(app: &'a TestApp, method: Method, uri: &str) -> Self {
+        Self {
+            app,
+            method,
+            uri: uri.to_string(),
+            headers: HeaderMap::new(),
+            body: Body::empty(),
+            checked: true,
+        }
+    }
+
+    pub fn header(mut self, name: HeaderName, value: &str) -> Self {
+        self.headers
+            .insert(name, HeaderValue::from_str(value).unwrap());
+        self
+    }
+
+    pub fn json(self, body: Value) -> Self {
+        self.body("application/json", body.to_string())
+    }
+
+    pub fn body(mut self, content_type: &str, body: impl Into<String>) -> Self {
+        self.body = Body::from(body.into());
+        self.header(header::CONTENT_TYPE, content_type)
+    }
+
+    /// Authenticates with the `token` cookie, as browsers do.
+    pub fn cookie(self, user: &TestUser) -> Self {
+        let cookie = format!("token={}", user.jwt);
+        self.header(header::COOKIE, &cookie)
+    }
+
+    pub fn bearer(self, user: &TestUser) -> Self {
+        let authorization = format!("Bearer {}", user.jwt);
+        self.header(header::AUTHORIZATION, &authorization)
+    }
+
+    /// Skips the OpenAPI check, for routes missing from the document.
+    pub fn unchecked(mut self) -> Self {
+        self.checked = false;
+        self
+    }
+
+    pub async fn send(self) -> TestResponse {
+        let mut request = Request::builder()
+            .method(self.method.clone())
+            .uri(&self.uri)
+            .body(self.body)
+            .unwrap();
+        *request.headers_mut() = self.headers;
+
+        let response = self.app.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let response = TestResponse {
+            status,
+            headers,
+            body,
+        };
+
+        if self.checked {
+            let path = self.uri.split('?').next().unwrap();
+            assert_conforms(&self.app.openapi, &self.method, path, &response);
+        }
+
+        response
+    }
+}
+
+#[derive(Debug)]
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl TestResponse {
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body)
+            .unwrap_or_else(|e| panic!("invalid json body ({e}): {}", self.text()))
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    pub fn header(&self, name: HeaderName) -> Option<&str> {
+        self.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.header(header::CONTENT_TYPE)
+            .map(|v| v.split(';').next().unwrap_or_default().trim())
+    }
+
+    /// Value of a cookie set by the response.
+    pub fn cookie(&self, name: &str) -> Option<String> {
+        self.headers
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .filter_map(|v| v.split(';').next()?.split_once('='))
+            .find(|(cookie_name, _)| *cookie_name == name)
+            .map(|(_, value)| value.to_string())
+    }
+}
@@ -0,0 +1,737 @@
+use axum::http::{HeaderName, StatusCode, header};
+use serde_json::json;
+use sqlx::PgPool;
+
+use super::harness::{TestApp, TestUser};
+
+const DESTINATION: &str = "https://example.com/landing";
+const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+
+async fn app_with_link(pool: PgPool) -> (TestApp, TestUser, String) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+    let link_id = app
+        .create_link(
+            &alice,
+            json!({ "redirected_url": DESTINATION, "label": "landing" }),
+        )
+        .await;
+
+    (app, alice, link_id)
+}
+
+#[sqlx::test]
+async fn view_redirects_and_counts_the_view(pool: PgPool) {
+    let (app, alice, link_id) = app_with_link(pool).await;
+
+    let response = app
+        .get(&format!("/view/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    assert_eq!(response.header(header::LOCATION), Some(DESTINATION));
+    assert_eq!(response.header(header::CACHE_CONTROL), Some("no-store"));
+
+    let response = app
+        .get(&format!("/get-views/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json(), json!(1));
+}
+
+#[sqlx::test]
+async fn view_forwards_the_path_after_the_link_id(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+    let link_id = app
+        .create_link(
+            &alice,
+            json!({ "redirected_url": "https://example.com/docs", "forward_path": true }),
+        )
+        .await;
+
+    // the catch-all route is not part of the OpenAPI document
+    let response = app
+        .get(&format!("/view/{link_id}/guide/intro"))
+        .bearer(&alice)
+        .unchecked()
+        .send()
+        .await;
+
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    assert_eq!(
+        response.header(header::LOCATION),
+        Some("https://example.com/docs/guide/intro")
+    );
+}
+
+#[sqlx::test]
+async fn view_with_plus_suffix_shows_the_preview(pool: PgPool) {
+    let (app, alice, link_id) = app_with_link(pool).await;
+
+    let response = app
+        .get(&format!("/view/{link_id}+"))
+        .bearer(&alice)
+        .send()
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.content_type(), Some("text/html"));
+    assert!(response.text().contains(DESTINATION));
+}
+
+#[sqlx::test]
+async fn view_of_unknown_link_is_not_found(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+
+    let response = app.get("/view/nope").bearer(&alice).send().await;
+
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn one_time_link_is_gone_after_the_first_view(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+    let link_id = app
+        .create_link(
+            &alice,
+            json!({ "redirected_url": DESTINATION, "one_time": true }),
+        )
+        .await;
+
+    let response = app
+        .get(&format!("/view/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+
+    let response = app
+        .get(&format!("/view/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::GONE);
+}
+
+#[sqlx::test]
+async fn crawlers_neither_use_up_nor_see_a_one_time_link(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+    let link_id = app
+        .create_link(
+            &alice,
+            json!({
+                "redirected_url": DESTINATION,
+                "one_time": true,
+                "og": { "title": "Launch" }
+            }),
+        )
+        .await;
+
+    let response = app
+        .get(&format!("/view/{link_id}"))
+        .bearer(&alice)
+        .header(header::USER_AGENT, "Slackbot-LinkExpanding 1.0")
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.text().contains("og:title"));
+    assert!(!response.text().contains(DESTINATION));
+
+    let response = app
+        .get(&format!("/get-views/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.json(), json!(0));
+
+    let response = app
+        .get(&format!("/view/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    assert_eq!(response.header(header::LOCATION), Some(DESTINATION));
+}
+
+#[sqlx::test]
+async fn short_links_are_followed_without_authentication(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+    let link_id = app
+        .create_link(
+            &alice,
+            json!({ "redirected_url": DESTINATION, "og": { "title": "Launch" } }),
+        )
+        .await;
+
+    let response = app
+        .get(&format!("/view/{link_id}"))
+        .header(header::USER_AGENT, "Slackbot-LinkExpanding 1.0")
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.text().contains(r#"og:url" content="https://"#));
+
+    let response = app.get(&format!("/view/{link_id}")).send().await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    assert_eq!(response.header(header::LOCATION), Some(DESTINATION));
+}
+
+#[sqlx::test]
+async fn crawlers_do_not_get_one_time_links_without_a_social_card(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+    let link_id = app
+        .create_link(
+            &alice,
+            json!({ "redirected_url": DESTINATION, "one_time": true }),
+        )
+        .await;
+
+    let response = app
+        .get(&format!("/view/{link_id}"))
+        .bearer(&alice)
+        .header(header::USER_AGENT, "Twitterbot/1.0")
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    let response = app
+        .get(&format!("/view/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+}
+
+#[sqlx::test]
+async fn preview_and_expand_show_the_destination(pool: PgPool) {
+    let (app, alice, link_id) = app_with_link(pool).await;
+
+    let response = app
+        .get(&format!("/preview/{link_id}"))
+        .cookie(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.text().contains(DESTINATION));
+
+    let response = app
+        .get(&format!("/expand/{link_id}"))
+        .cookie(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body = response.json();
+    assert_eq!(body["id"], link_id.as_str());
+    assert_eq!(body["destination"], DESTINATION);
+    assert_eq!(body["resolved_destination"], DESTINATION);
+    assert_eq!(body["label"], "landing");
+}
+
+#[sqlx::test]
+async fn expand_resolves_the_destination_for_the_caller(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+    let link_id = app
+        .create_link(
+            &alice,
+            json!({
+                "redirected_url": DESTINATION,
+                "utm": { "source": "newsletter" },
+                "languages": { "locales": { "de": "https://example.com/de" } }
+            }),
+        )
+        .await;
+
+    let response = app
+        .get(&format!("/expand/{link_id}"))
+        .bearer(&alice)
+        .header(header::ACCEPT_LANGUAGE, "de-DE,de;q=0.9")
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body = response.json();
+    assert_eq!(body["destination"], DESTINATION);
+    assert_eq!(
+        body["resolved_destination"],
+        "https://example.com/de?utm_source=newsletter"
+    );
+}
+
+#[sqlx::test]
+async fn preview_and_expand_reject_one_time_and_unknown_links(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+    let link_id = app
+        .create_link(
+            &alice,
+            json!({ "redirected_url": DESTINATION, "one_time": true }),
+        )
+        .await;
+
+    for route in ["preview", "expand"] {
+        let response = app
+            .get(&format!("/{route}/{link_id}"))
+            .bearer(&alice)
+            .send()
+            .await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN, "{route}");
+
+        let response = app
+            .get(&format!("/{route}/nope"))
+            .bearer(&alice)
+            .send()
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND, "{route}");
+    }
+}
+
+#[sqlx::test]
+async fn views_by_country_and_variant(pool: PgPool) {
+    let (app, alice, link_id) = app_with_link(pool).await;
+    app.get(&format!("/view/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+
+    for route in ["countries", "variants"] {
+        let response = app
+            .get(&format!("/get-views/{link_id}/{route}"))
+            .bearer(&alice)
+            .send()
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{route}");
+        assert!(response.json().is_array(), "{route}");
+    }
+}
+
+#[sqlx::test]
+async fn views_of_unknown_link_are_not_found(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+
+    for route in [
+        "/get-views/nope",
+        "/get-views/nope/countries",
+        "/get-views/nope/variants",
+    ] {
+        let response = app.get(route).bearer(&alice).send().await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND, "{route}");
+    }
+}
+
+#[sqlx::test]
+async fn create_link_rejects_invalid_options(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+
+    let response = app
+        .post("/create-link")
+        .bearer(&alice)
+        .json(json!({ "redirected_url": DESTINATION, "fallback_url": "ftp://example.com" }))
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app
+        .post("/create-link")
+        .bearer(&alice)
+        .json(json!({ "redirected_url": "javascript:alert(document.cookie)" }))
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app
+        .post("/create-link")
+        .bearer(&alice)
+        .json(json!({ "redirected_url": DESTINATION, "utm_template": "unknown" }))
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn create_link_replays_idempotent_retries(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+    let body = json!({ "redirected_url": DESTINATION });
+
+    let mut link_ids = vec![];
+    for _ in 0..2 {
+        let response = app
+            .post("/create-link")
+            .bearer(&alice)
+            .header(IDEMPOTENCY_KEY, "retry-1")
+            .json(body.clone())
+            .send()
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        link_ids.push(response.json()["value"].clone());
+    }
+    assert_eq!(link_ids[0], link_ids[1]);
+
+    let response = app
+        .post("/create-link")
+        .bearer(&alice)
+        .header(IDEMPOTENCY_KEY, "retry-1")
+        .json(json!({ "redirected_url": "https://example.com/other" }))
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app
+        .post("/create-link")
+        .bearer(&alice)
+        .header(IDEMPOTENCY_KEY, " ")
+        .json(body)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn create_links_in_bulk(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+
+    let response = app
+        .post("/create-links")
+        .bearer(&alice)
+        .json(json!({ "links": [
+            { "redirected_url": "https://example.com/a" },
+            { "redirected_url": "https://example.com/b", "label": "b" },
+        ] }))
+        .send()
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["link_ids"].as_array().unwrap().len(), 2);
+}
+
+#[sqlx::test]
+async fn create_links_reports_invalid_rows(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+
+    let response = app
+        .post("/create-links")
+        .bearer(&alice)
+        .json(json!({ "links": [
+            { "redirected_url": "https://example.com/a" },
+            { "redirected_url": "not a url" },
+        ] }))
+        .send()
+        .await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    let row_errors = response.json();
+    assert_eq!(row_errors.as_array().unwrap().len(), 1);
+    assert_eq!(row_errors[0]["row"], 2);
+}
+
+#[sqlx::test]
+async fn import_links_from_csv(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+
+    let response = app
+        .post("/import-links")
+        .bearer(&alice)
+        .body(
+            "text/csv",
+            "long_url,title\nhttps://example.com/a,A\nhttps://example.com/b,B\n",
+        )
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["link_ids"].as_array().unwrap().len(), 2);
+
+    let response = app
+        .post("/import-links")
+        .bearer(&alice)
+        .body("text/csv", "title\nA\n")
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn export_links_as_json_and_csv(pool: PgPool) {
+    let (app, alice, link_id) = app_with_link(pool).await;
+
+    let response = app.get("/export-links").bearer(&alice).send().await;
+    assert_eq!(response.status, StatusCode::OK);
+    let rows = response.json();
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+    assert_eq!(rows[0]["id"], link_id.as_str());
+
+    let response = app
+        .get("/export-links?format=csv")
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.content_type(), Some("text/csv"));
+    assert!(response.text().contains(DESTINATION));
+}
+
+#[sqlx::test]
+async fn csv_export_escapes_formulas_and_imports_unchanged(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+    let label = "=HYPERLINK(\"https://evil.example\")";
+    app.create_link(
+        &alice,
+        json!({ "redirected_url": DESTINATION, "label": label }),
+    )
+    .await;
+
+    let response = app
+        .get("/export-links?format=csv")
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let csv = response.text();
+    assert!(csv.contains("'=HYPERLINK"));
+
+    let bob = app.user("bob").await;
+    let response = app
+        .post("/import-links")
+        .bearer(&bob)
+        .body("text/csv", &csv)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app.get("/export-links").bearer(&bob).send().await;
+    assert_eq!(response.json()[0]["label"], label);
+}
+
+#[sqlx::test]
+async fn reuse_existing_skips_used_up_and_trashed_links(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+    let one_time =
+        json!({ "redirected_url": DESTINATION, "one_time": true, "reuse_existing": true });
+    let consumed = app.create_link(&alice, one_time.clone()).await;
+    app.get(&format!("/view/{consumed}")).send().await;
+    let trashed = app
+        .create_link(
+            &alice,
+            json!({ "redirected_url": DESTINATION, "reuse_existing": true }),
+        )
+        .await;
+    app.delete(&format!("/delete-link/{trashed}"))
+        .bearer(&alice)
+        .send()
+        .await;
+
+    assert_ne!(app.create_link(&alice, one_time).await, consumed);
+    let link_id = app
+        .create_link(
+            &alice,
+            json!({ "redirected_url": DESTINATION, "reuse_existing": true }),
+        )
+        .await;
+    assert_ne!(link_id, trashed);
+    let reused = app
+        .create_link(
+            &alice,
+            json!({ "redirected_url": format!("{DESTINATION}?utm_source=mail"), "reuse_existing": true }),
+        )
+        .await;
+    assert_eq!(reused, link_id);
+}
+
+#[sqlx::test]
+async fn bulk_create_and_import_reuse_existing_links_per_row(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+    let link_id = app
+        .create_link(
+            &alice,
+            json!({ "redirected_url": DESTINATION, "reuse_existing": true }),
+        )
+        .await;
+
+    let response = app
+        .post("/create-links")
+        .bearer(&alice)
+        .json(json!({ "links": [
+            { "redirected_url": DESTINATION, "reuse_existing": true },
+            { "redirected_url": DESTINATION },
+        ] }))
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let link_ids = response.json()["link_ids"].clone();
+    assert_eq!(link_ids[0]["value"], link_id.as_str());
+    assert_ne!(link_ids[1]["value"], link_id.as_str());
+
+    let response = app
+        .post("/import-links?reuse_existing=true")
+        .bearer(&alice)
+        .body("text/csv", format!("url\n{DESTINATION}\n"))
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["link_ids"][0]["value"], link_id.as_str());
+}
+
+#[sqlx::test]
+async fn deleted_link_moves_to_the_trash_and_back(pool: PgPool) {
+    let (app, alice, link_id) = app_with_link(pool).await;
+
+    let response = app
+        .delete(&format!("/delete-link/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json(), json!(true));
+
+    let response = app
+        .get(&format!("/view/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.get("/trash-links").bearer(&alice).send().await;
+    assert_eq!(response.status, StatusCode::OK);
+    let trashed = response.json();
+    assert_eq!(trashed.as_array().unwrap().len(), 1);
+    assert_eq!(trashed[0]["id"], link_id.as_str());
+
+    let response = app
+        .post(&format!("/restore-link/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app
+        .get(&format!("/view/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+
+    let response = app
+        .post(&format!("/restore-link/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn only_the_owner_manages_a_link(pool: PgPool) {
+    let (app, alice, link_id) = app_with_link(pool).await;
+    let bob = app.user("bob").await;
+
+    let response = app
+        .delete(&format!("/delete-link/{link_id}"))
+        .bearer(&bob)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    let response = app
+        .get(&format!("/link-health/{link_id}"))
+        .bearer(&bob)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    app.delete(&format!("/delete-link/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    let response = app
+        .post(&format!("/restore-link/{link_id}"))
+        .bearer(&bob)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test]
+async fn unknown_links_can_not_be_managed(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+
+    let response = app.delete("/delete-link/nope").bearer(&alice).send().await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.post("/restore-link/nope").bearer(&alice).send().await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.get("/link-health/nope").bearer(&alice).send().await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn health_report_lists_unchecked_links(pool: PgPool) {
+    let (app, alice, link_id) = app_with_link(pool).await;
+
+    let response = app.get("/health-report").bearer(&alice).send().await;
+    assert_eq!(response.status, StatusCode::OK);
+    let report = response.json();
+    assert_eq!(report.as_array().unwrap().len(), 1);
+    assert_eq!(report[0]["id"], link_id.as_str());
+    assert_eq!(report[0]["failures"], 0);
+
+    let response = app
+        .get("/health-report?broken_only=true")
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.json(), json!([]));
+
+    let response = app
+        .get(&format!("/link-health/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json(), json!([]));
+}
+
+#[sqlx::test]
+async fn link_routes_require_auth(pool: PgPool) {
+    let (app, _, link_id) = app_with_link(pool).await;
+
+    let requests = [
+        app.get(&format!("/preview/{link_id}")),
+        app.get(&format!("/expand/{link_id}")),
+        app.get(&format!("/get-views/{link_id}")),
+        app.get(&format!("/get-views/{link_id}/countries")),
+        app.get(&format!("/get-views/{link_id}/variants")),
+        app.post("/create-link")
+            .json(json!({ "redirected_url": DESTINATION })),
+        app.post("/create-links").json(json!({ "links": [] })),
+        app.post("/import-links")
+            .body("text/csv", "url\nhttps://example.com/\n"),
+        app.get("/export-links"),
+        app.delete(&format!("/delete-link/{link_id}")),
+        app.post(&format!("/restore-link/{link_id}")),
+        app.get("/trash-links"),
+        app.get("/health-report"),
+        app.get(&format!("/link-health/{link_id}")),
+    ];
+
+    for request in requests {
+        let response = request.send().await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,12 @@
+//! End-to-end tests of the HTTP API. Every test gets its own Postgres database through
+//! `#[sqlx::test]` and drives the full router; Redis is left unreachable, so links are
+//! cached in memory only. Every response is checked against the OpenAPI document.
+
+mod harness;
+mod openapi;
+
+mod auth;
+mod links;
+mod replicas;
+mod status;
+mod users;
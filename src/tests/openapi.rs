@@ -0,0 +1,199 @@
+//! Checks responses against the generated OpenAPI document: the route and status must be
+//! documented, and JSON bodies must match the documented schema. Only the schema keywords
+//! utoipa emits are supported.
+
+use axum::http::Method;
+use serde_json::{Map, Value};
+
+use super::harness::TestResponse;
+
+pub fn assert_conforms(doc: &Value, method: &Method, path: &str, response: &TestResponse) {
+    let (template, operation) = find_operation(doc, method, path)
+        .unwrap_or_else(|| panic!("{method} {path} is not documented"));
+
+    let status = response.status.as_str();
+    let documented = operation["responses"]
+        .get(status)
+        .unwrap_or_else(|| panic!("{method} {template} does not document status {status}"));
+
+    // a documented body is not required, error responses are often left empty
+    let Some(content) = documented.get("content").and_then(Value::as_object) else {
+        return;
+    };
+    let Some(content_type) = response.content_type() else {
+        assert!(
+            response.body.is_empty(),
+            "{method} {template} {status} has a body without a content type"
+        );
+        return;
+    };
+    let media = content.get(content_type).unwrap_or_else(|| {
+        panic!("{method} {template} {status} does not document content type {content_type}")
+    });
+
+    if content_type != "application/json" {
+        return;
+    }
+    let Some(schema) = media.get("schema") else {
+        return;
+    };
+    if let Err(e) = validate(doc, schema, &response.json(), "$") {
+        panic!(
+            "{method} {template} {status} does not match its schema: {e}\n{}",
+            response.text()
+        );
+    }
+}
+
+fn find_operation<'a>(doc: &'a Value, method: &Method, path: &str) -> Option<(&'a str, &'a Value)> {
+    let method = method.as_str().to_lowercase();
+    doc["paths"]
+        .as_object()?
+        .iter()
+        .filter(|(template, _)| matches_template(template, path))
+        .find_map(|(template, item)| Some((template.as_str(), item.get(&method)?)))
+}
+
+fn matches_template(template: &str, path: &str) -> bool {
+    let template: Vec<&str> = template.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+
+    template.len() == path.len()
+        && template.iter().zip(&path).all(|(expected, actual)| {
+            expected == actual || (expected.starts_with('{') && expected.ends_with('}'))
+        })
+}
+
+fn resolve<'a>(doc: &'a Value, schema: &'a Value) -> Result<&'a Value, String> {
+    match schema.get("$ref").and_then(Value::as_str) {
+        Some(reference) => {
+            let pointer = reference
+                .strip_prefix('#')
+                .ok_or_else(|| format!("unsupported reference {reference}"))?;
+            let target = doc
+                .pointer(pointer)
+                .ok_or_else(|| format!("unresolved reference {reference}"))?;
+            resolve(doc, target)
+        }
+        None => Ok(schema),
+    }
+}
+
+fn validate(doc: &Value, schema: &Value, value: &Value, at: &str) -> Result<(), String> {
+    let schema = resolve(doc, schema)?;
+
+    if let Some(all_of) = schema.get("allOf").and_then(Value::as_array) {
+        for sub_schema in all_of {
+            validate(doc, sub_schema, value, at)?;
+        }
+    }
+    if let Some(one_of) = schema.get("oneOf").and_then(Value::as_array) {
+        let matching = one_of
+            .iter()
+            .filter(|sub_schema| validate(doc, sub_schema, value, at).is_ok())
+            .count();
+        if matching != 1 {
+            return Err(format!(
+                "{at}: {matching} of the oneOf schemas match {value}"
+            ));
+        }
+    }
+    if let Some(any_of) = schema.get("anyOf").and_then(Value::as_array)
+        && !any_of
+            .iter()
+            .any(|sub_schema| validate(doc, sub_schema, value, at).is_ok())
+    {
+        return Err(format!("{at}: none of the anyOf schemas match {value}"));
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        return Err(format!("{at}: {value} is not one of {allowed:?}"));
+    }
+
+    match schema.get("type") {
+        Some(Value::String(expected)) => check_type(expected, value, at)?,
+        Some(Value::Array(expected))
+            if !expected
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|expected| check_type(expected, value, at).is_ok()) =>
+        {
+            return Err(format!("{at}: {value} is none of {expected:?}"));
+        }
+        _ => {}
+    }
+
+    match value {
+        Value::Object(object) => validate_object(doc, schema, object, at),
+        Value::Array(items) => match schema.get("items") {
+            Some(item_schema) => items
+                .iter()
+                .enumerate()
+                .try_for_each(|(i, item)| validate(doc, item_schema, item, &format!("{at}[{i}]"))),
+            None => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+fn check_type(expected: &str, value: &Value, at: &str) -> Result<(), String> {
+    let matches = match expected {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => return Err(format!("{at}: unsupported type {expected}")),
+    };
+
+    if matches {
+        Ok(())
+    } else {
+        Err(format!("{at}: expected {expected}, got {value}"))
+    }
+}
+
+fn validate_object(
+    doc: &Value,
+    schema: &Value,
+    object: &Map<String, Value>,
+    at: &str,
+) -> Result<(), String> {
+    let properties = schema.get("properties").and_then(Value::as_object);
+
+    for required in schema
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+    {
+        if !object.contains_key(required) {
+            return Err(format!("{at}: missing required property {required}"));
+        }
+    }
+
+    for (name, value) in object {
+        let at = format!("{at}.{name}");
+        match properties.and_then(|properties| properties.get(name)) {
+            Some(property_schema) => validate(doc, property_schema, value, &at)?,
+            None => match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    return Err(format!("{at}: undocumented property"));
+                }
+                Some(additional @ Value::Object(_)) => validate(doc, additional, value, &at)?,
+                // utoipa documents every field of a struct
+                _ if properties.is_some() => {
+                    return Err(format!("{at}: undocumented property"));
+                }
+                _ => {}
+            },
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+//! A `replica` schema in the test database stands in for the read replica. Replication is
+//! played by copying rows into it, with a destination that tells the two reads apart.
+
+use std::time::Duration;
+
+use axum::http::{StatusCode, header};
+use serde_json::json;
+use sqlx::PgPool;
+
+use super::harness::{TestApp, TestUser};
+
+const DESTINATION: &str = "https://example.com/landing";
+const REPLICA_DESTINATION: &str = "https://example.com/from-replica";
+const SHORT_LAG_GUARD: Duration = Duration::from_millis(50);
+const LONG_LAG_GUARD: Duration = Duration::from_secs(60);
+
+async fn replica_pool(pool: &PgPool) -> PgPool {
+    sqlx::query("CREATE SCHEMA replica")
+        .execute(pool)
+        .await
+        .unwrap();
+    for table in ["links", "link_country_views", "link_variant_views"] {
+        sqlx::query(&format!(
+            "CREATE TABLE replica.{table} (LIKE public.{table} INCLUDING ALL)"
+        ))
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    let options = (*pool.connect_options())
+        .clone()
+        .options([("search_path", "replica")]);
+    PgPool::connect_lazy_with(options)
+}
+
+async fn replicate_link(pool: &PgPool, link_id: &str) {
+    sqlx::query("INSERT INTO replica.links SELECT * FROM public.links WHERE id = $1")
+        .bind(link_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE replica.links SET redirect_url = $1 WHERE id = $2")
+        .bind(REPLICA_DESTINATION)
+        .bind(link_id)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+async fn app_with_link(pool: PgPool, lag_guard: Duration) -> (TestApp, TestUser, String) {
+    let replica = replica_pool(&pool).await;
+    let app = TestApp::with_read_replica(pool, replica, lag_guard).await;
+    let alice = app.user("alice").await;
+    let link_id = app
+        .create_link(&alice, json!({ "redirected_url": DESTINATION }))
+        .await;
+
+    (app, alice, link_id)
+}
+
+#[sqlx::test]
+async fn redirect_reads_the_replica_once_the_lag_guard_passed(pool: PgPool) {
+    let (app, alice, link_id) = app_with_link(pool.clone(), SHORT_LAG_GUARD).await;
+    replicate_link(&pool, &link_id).await;
+    tokio::time::sleep(SHORT_LAG_GUARD * 2).await;
+
+    let response = app
+        .get(&format!("/view/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    assert_eq!(response.header(header::LOCATION), Some(REPLICA_DESTINATION));
+}
+
+#[sqlx::test]
+async fn redirect_reads_the_primary_within_the_lag_guard(pool: PgPool) {
+    let (app, alice, link_id) = app_with_link(pool.clone(), LONG_LAG_GUARD).await;
+    replicate_link(&pool, &link_id).await;
+
+    let response = app
+        .get(&format!("/view/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    assert_eq!(response.header(header::LOCATION), Some(DESTINATION));
+}
+
+#[sqlx::test]
+async fn link_missing_on_the_replica_is_read_from_the_primary(pool: PgPool) {
+    let (app, alice, link_id) = app_with_link(pool, SHORT_LAG_GUARD).await;
+    tokio::time::sleep(SHORT_LAG_GUARD * 2).await;
+
+    let response = app
+        .get(&format!("/view/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    assert_eq!(response.header(header::LOCATION), Some(DESTINATION));
+}
+
+#[sqlx::test]
+async fn views_are_counted_on_the_primary(pool: PgPool) {
+    let (app, alice, link_id) = app_with_link(pool.clone(), SHORT_LAG_GUARD).await;
+    replicate_link(&pool, &link_id).await;
+    tokio::time::sleep(SHORT_LAG_GUARD * 2).await;
+
+    let response = app
+        .get(&format!("/view/{link_id}"))
+        .bearer(&alice)
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+
+    let views: i64 = sqlx::query_scalar("SELECT views FROM public.links WHERE id = $1")
+        .bind(&link_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(views, 1);
+}
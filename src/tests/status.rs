@@ -0,0 +1,87 @@
+use std::{env, fs, process};
+
+use axum::http::{StatusCode, header};
+use serde_json::json;
+use sqlx::PgPool;
+
+use super::harness::TestApp;
+use crate::{AppState, config::ConfigSettings, container::build_container_with};
+
+#[sqlx::test]
+async fn health_is_degraded_without_redis(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+
+    let response = app.get("/health").send().await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    let body = response.json();
+    assert_eq!(body["status"], "degraded");
+    assert_eq!(body["redis"], "disconnected");
+}
+
+#[tokio::test]
+async fn sqlite_install_without_redis_is_healthy() {
+    let database = env::temp_dir().join(format!("short-link-{}.db", process::id()));
+    let config: ConfigSettings = serde_json::from_value(json!({
+        "database": { "url": format!("sqlite://{}", database.display()) },
+        "server": { "host": "127.0.0.1", "port": 0 },
+    }))
+    .unwrap();
+    let container = build_container_with(config).await;
+    let app = TestApp::with_state(AppState::new(&container));
+
+    let response = app.get("/health").send().await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body = response.json();
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["redis"], "disabled");
+
+    let alice = app.user("alice").await;
+    let link_id = app
+        .create_link(&alice, json!({ "redirected_url": "https://example.com/" }))
+        .await;
+    let response = app.get(&format!("/view/{link_id}")).send().await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    assert_eq!(
+        response.header(header::LOCATION),
+        Some("https://example.com/")
+    );
+
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = fs::remove_file(format!("{}{suffix}", database.display()));
+    }
+}
+
+#[sqlx::test]
+async fn cache_stats_count_local_hits(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+    let link_id = app
+        .create_link(
+            &alice,
+            serde_json::json!({ "redirected_url": "https://example.com/" }),
+        )
+        .await;
+
+    for _ in 0..2 {
+        let response = app
+            .get(&format!("/expand/{link_id}"))
+            .bearer(&alice)
+            .send()
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+    }
+
+    let response = app.get("/cache-stats").bearer(&alice).send().await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.json()["local"]["hits"].as_u64().unwrap() >= 1);
+}
+
+#[sqlx::test]
+async fn cache_stats_require_authentication(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+
+    let response = app.get("/cache-stats").send().await;
+
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,74 @@
+use axum::http::StatusCode;
+use serde_json::json;
+use sqlx::PgPool;
+
+use super::harness::TestApp;
+
+#[sqlx::test]
+async fn get_user_info_hides_password(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+    let bob = app.user("bob").await;
+
+    let response = app
+        .get(&format!("/user/{}", bob.id))
+        .bearer(&alice)
+        .send()
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    let body = response.json();
+    assert_eq!(body["id"], bob.id);
+    assert_eq!(body["email"], "bob@example.com");
+    assert!(body.get("password").is_none());
+}
+
+#[sqlx::test]
+async fn get_user_info_of_unknown_user_is_not_found(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+
+    let response = app
+        .get(&format!("/user/{}", alice.id + 1000))
+        .bearer(&alice)
+        .send()
+        .await;
+
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn change_name_renames_the_caller(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let alice = app.user("alice").await;
+
+    let response = app
+        .post("/change-name")
+        .cookie(&alice)
+        .json(json!({ "name": "Alice Liddell" }))
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app
+        .get(&format!("/user/{}", alice.id))
+        .cookie(&alice)
+        .send()
+        .await;
+    assert_eq!(response.json()["name"], "Alice Liddell");
+}
+
+#[sqlx::test]
+async fn user_routes_require_auth(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+
+    let response = app.get("/user/1").send().await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .post("/change-name")
+        .json(json!({ "name": "nobody" }))
+        .send()
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
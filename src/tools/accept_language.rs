@@ -0,0 +1,48 @@
+/// Language ranges from an `Accept-Language` header, most preferred first. Ranges with
+/// `q=0` are dropped, equal weights keep their header order.
+pub fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut ranges: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|part| {
+            let mut params = part.split(';');
+            let range = params.next()?.trim().to_lowercase();
+            if range.is_empty() {
+                return None;
+            }
+
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (quality > 0.0).then_some((range, quality))
+        })
+        .collect();
+
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranges.into_iter().map(|(range, _)| range).collect()
+}
+
+fn primary_subtag(tag: &str) -> &str {
+    tag.split('-').next().unwrap_or(tag)
+}
+
+/// Picks the best of `available` language tags for the header. For each requested range,
+/// in preference order, an exact match wins, then a tag with the same primary language
+/// (`de-AT` matches `de`, `de` matches `de-DE`). `*` matches nothing, so the caller's
+/// fallback applies.
+pub fn negotiate_language<'a>(
+    header: &str,
+    available: impl IntoIterator<Item = &'a str> + Clone,
+) -> Option<&'a str> {
+    parse_accept_language(header).iter().find_map(|range| {
+        available
+            .clone()
+            .into_iter()
+            .find(|tag| tag.eq_ignore_ascii_case(range))
+            .or_else(|| {
+                available.clone().into_iter().find(|tag| {
+                    primary_subtag(tag).eq_ignore_ascii_case(primary_subtag(range)) && range != "*"
+                })
+            })
+    })
+}
@@ -0,0 +1,24 @@
+use solar::trx_factory::{SqlxTrxFactory, TrxContext, TrxFactory, TrxFactoryError};
+
+use super::sqlite_trx::SqliteTrxFactory;
+
+/// Transaction factory of the configured database. An enum since `TrxFactory` can not be
+/// used as a trait object.
+#[derive(Clone)]
+pub enum AnyTrxFactory {
+    Postgres(SqlxTrxFactory),
+    Sqlite(SqliteTrxFactory),
+}
+
+impl TrxFactory for AnyTrxFactory {
+    async fn begin<R, E, F>(&self, f: F) -> Result<R, E>
+    where
+        F: AsyncFnOnce(TrxContext) -> Result<R, E>,
+        E: From<TrxFactoryError>,
+    {
+        match self {
+            AnyTrxFactory::Postgres(trx_factory) => trx_factory.begin(f).await,
+            AnyTrxFactory::Sqlite(trx_factory) => trx_factory.begin(f).await,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use utoipa::ToSchema;
+
+use super::redis_connection::RedisStatus;
+
+/// Hit and miss counters of one cache tier.
+#[derive(Debug, Default)]
+pub struct TierCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl TierCounters {
+    pub fn hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> TierStats {
+        TierStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct TierStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Counters since startup, per cache tier.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct CacheStats {
+    /// In-process tier, all zero when it is disabled.
+    pub local: TierStats,
+    pub local_entries: u64,
+    pub redis: TierStats,
+    /// Unknown link ids rejected by the Bloom filter.
+    pub bloom_rejections: u64,
+    pub redis_status: RedisStatus,
+    /// Redis commands skipped or failed while it was unreachable, lookups went to the
+    /// database.
+    pub redis_bypassed: u64,
+}
@@ -0,0 +1,91 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// Calls go through.
+    Closed,
+    /// Calls are skipped until the breaker tries again.
+    Open,
+    /// A single trial call decides whether the breaker closes or opens again.
+    HalfOpen,
+}
+
+struct Inner {
+    state: BreakerState,
+    failures: u32,
+    /// When the breaker opened, or when the half-open trial started.
+    since: Instant,
+}
+
+/// Opens after `failure_threshold` consecutive failures. After `open_for` one trial call is
+/// let through, its outcome closes the breaker or opens it again.
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_for: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, open_for: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            open_for,
+            inner: Mutex::new(Inner {
+                state: BreakerState::Closed,
+                failures: 0,
+                since: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn allow(&self) -> bool {
+        let mut inner = self.inner.lock().expect("circuit breaker lock poisoned");
+        match inner.state {
+            BreakerState::Closed => true,
+            // a trial that never reported back must not keep the breaker stuck
+            BreakerState::Open | BreakerState::HalfOpen
+                if inner.since.elapsed() >= self.open_for =>
+            {
+                inner.state = BreakerState::HalfOpen;
+                inner.since = Instant::now();
+                true
+            }
+            BreakerState::Open | BreakerState::HalfOpen => false,
+        }
+    }
+
+    /// `true` if this closed the breaker.
+    pub fn record_success(&self) -> bool {
+        let mut inner = self.inner.lock().expect("circuit breaker lock poisoned");
+        let was_closed = inner.state == BreakerState::Closed;
+        inner.state = BreakerState::Closed;
+        inner.failures = 0;
+        !was_closed
+    }
+
+    /// `true` if this opened a closed breaker, a failed trial only opens it again.
+    pub fn record_failure(&self) -> bool {
+        let mut inner = self.inner.lock().expect("circuit breaker lock poisoned");
+        let was_closed = inner.state == BreakerState::Closed;
+        inner.failures = inner.failures.saturating_add(1);
+        if inner.state == BreakerState::HalfOpen || inner.failures >= self.failure_threshold {
+            inner.state = BreakerState::Open;
+            inner.since = Instant::now();
+            return was_closed;
+        }
+        false
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.inner
+            .lock()
+            .expect("circuit breaker lock poisoned")
+            .state
+    }
+}
@@ -0,0 +1,87 @@
+use std::net::IpAddr;
+
+use http::HeaderMap;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// An address or a CIDR range, e.g. `10.0.0.1` or `10.0.0.0/8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    pub fn parse(value: &str) -> Option<Self> {
+        let (addr, prefix_len) = match value.trim().split_once('/') {
+            Some((addr, prefix_len)) => (addr.parse::<IpAddr>().ok()?, prefix_len.parse().ok()?),
+            None => {
+                let addr = value.trim().parse::<IpAddr>().ok()?;
+                (addr, if addr.is_ipv4() { 32 } else { 128 })
+            }
+        };
+
+        let max_len = if addr.is_ipv4() { 32 } else { 128 };
+        if prefix_len > max_len {
+            return None;
+        }
+
+        Some(Self { addr, prefix_len })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(range), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u32::from(range) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(range), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u128::from(range) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Picks the client address for a request. `X-Forwarded-For` is only honoured when the peer
+/// is a trusted proxy; the list is then walked right to left, skipping trusted proxies, and the
+/// first untrusted hop is the client.
+pub struct ClientIpResolver {
+    trusted_proxies: Vec<IpRange>,
+}
+
+impl ClientIpResolver {
+    pub fn new(trusted_proxies: Vec<IpRange>) -> Self {
+        Self { trusted_proxies }
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|range| range.contains(ip))
+    }
+
+    pub fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+
+        let hops: Vec<IpAddr> = headers
+            .get_all(X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|hop| hop.trim().parse::<IpAddr>().ok())
+            .collect();
+
+        hops.iter()
+            .rev()
+            .find(|hop| !self.is_trusted(**hop))
+            .or(hops.first())
+            .copied()
+            .unwrap_or(peer)
+    }
+}
@@ -0,0 +1,45 @@
+use std::{
+    fmt::Debug,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+};
+
+/// Logs the first failure of a run and the recovery, so an outage hit on every request or
+/// tick leaves two lines instead of one per attempt.
+pub struct FailureLog {
+    action: &'static str,
+    failing: AtomicBool,
+    suppressed: AtomicU64,
+}
+
+impl FailureLog {
+    /// `action` completes "failed to ...".
+    pub const fn new(action: &'static str) -> Self {
+        Self {
+            action,
+            failing: AtomicBool::new(false),
+            suppressed: AtomicU64::new(0),
+        }
+    }
+
+    pub fn failed(&self, e: impl Debug) {
+        if self.failing.swap(true, Ordering::AcqRel) {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        println!(
+            "failed to {}: {e:?}, not logged again until it succeeds",
+            self.action
+        );
+    }
+
+    pub fn succeeded(&self) {
+        if !self.failing.load(Ordering::Acquire) || !self.failing.swap(false, Ordering::AcqRel) {
+            return;
+        }
+        let suppressed = self.suppressed.swap(0, Ordering::Relaxed);
+        println!(
+            "{} succeeded again after {suppressed} more failures",
+            self.action
+        );
+    }
+}
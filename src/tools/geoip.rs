@@ -0,0 +1,110 @@
+use std::{
+    net::IpAddr,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use maxminddb::{Reader, geoip2};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeoLocation {
+    /// ISO 3166-1 alpha-2 country code
+    pub country: String,
+    /// ISO 3166-2 subdivision code, without the country prefix
+    pub region: Option<String>,
+}
+
+struct LoadedDatabase {
+    reader: Arc<Reader<Vec<u8>>>,
+    modified: Option<SystemTime>,
+}
+
+/// Looks up client locations in a local MaxMind `.mmdb` file. The file is reloaded when its
+/// modification time changes, so it can be replaced without a restart.
+pub struct GeoIpResolver {
+    database_path: Option<PathBuf>,
+    database: RwLock<Option<LoadedDatabase>>,
+}
+
+impl GeoIpResolver {
+    pub fn disabled() -> Self {
+        Self {
+            database_path: None,
+            database: RwLock::new(None),
+        }
+    }
+
+    pub fn open(database_path: impl Into<PathBuf>) -> Self {
+        let resolver = Self {
+            database_path: Some(database_path.into()),
+            database: RwLock::new(None),
+        };
+        if let Err(e) = resolver.reload_if_changed() {
+            println!("failed to load geoip database: {e:?}");
+        }
+
+        resolver
+    }
+
+    pub fn reload_if_changed(&self) -> eyre::Result<bool> {
+        let Some(path) = &self.database_path else {
+            return Ok(false);
+        };
+
+        let modified = std::fs::metadata(path)?.modified().ok();
+        {
+            let database = self.database.read().expect("geoip lock poisoned");
+            if let Some(database) = database.as_ref()
+                && modified.is_some()
+                && database.modified == modified
+            {
+                return Ok(false);
+            }
+        }
+
+        let reader = Reader::open_readfile(path)
+            .map_err(|e| eyre::eyre!("failed to open geoip database: {e}"))?;
+        *self.database.write().expect("geoip lock poisoned") = Some(LoadedDatabase {
+            reader: Arc::new(reader),
+            modified,
+        });
+
+        Ok(true)
+    }
+
+    pub fn lookup(&self, ip: IpAddr) -> Option<GeoLocation> {
+        let reader = self
+            .database
+            .read()
+            .expect("geoip lock poisoned")
+            .as_ref()
+            .map(|database| database.reader.clone())?;
+
+        let city: geoip2::City = reader.lookup(ip).ok()?;
+        let country = city.country.and_then(|c| c.iso_code)?.to_uppercase();
+        let region = city
+            .subdivisions
+            .and_then(|subdivisions| subdivisions.into_iter().next())
+            .and_then(|subdivision| subdivision.iso_code)
+            .map(str::to_uppercase);
+
+        Some(GeoLocation { country, region })
+    }
+}
+
+pub fn spawn_reloader(resolver: Arc<GeoIpResolver>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            match resolver.reload_if_changed() {
+                Ok(true) => println!("geoip database reloaded"),
+                Ok(false) => {}
+                Err(e) => println!("failed to reload geoip database: {e:?}"),
+            }
+        }
+    });
+}
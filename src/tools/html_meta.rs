@@ -0,0 +1,228 @@
+use url::Url;
+
+const MAX_VALUE_LEN: usize = 512;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub favicon: Option<String>,
+    pub image: Option<String>,
+}
+
+struct Tag<'a> {
+    name: String,
+    attrs: Vec<(String, &'a str)>,
+    end: usize,
+}
+
+impl Tag<'_> {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(attr, _)| attr == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// Parses the tag starting at `start` (the `<`). Only what is needed for `<head>` metadata:
+/// the tag name and its attributes.
+fn parse_tag(html: &str, start: usize) -> Option<Tag<'_>> {
+    let bytes = html.as_bytes();
+    let mut i = start + 1;
+    let name_start = i;
+    while i < bytes.len()
+        && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'/' || bytes[i] == b'!')
+    {
+        i += 1;
+    }
+    let name = html[name_start..i].to_ascii_lowercase();
+
+    let mut attrs = Vec::new();
+    loop {
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b'/') {
+            i += 1;
+        }
+        if i >= bytes.len() {
+            return None;
+        }
+        if bytes[i] == b'>' {
+            return Some(Tag {
+                name,
+                attrs,
+                end: i + 1,
+            });
+        }
+
+        let attr_start = i;
+        while i < bytes.len()
+            && !bytes[i].is_ascii_whitespace()
+            && !matches!(bytes[i], b'=' | b'>' | b'/')
+        {
+            i += 1;
+        }
+        let attr = html[attr_start..i].to_ascii_lowercase();
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i >= bytes.len() || bytes[i] != b'=' {
+            attrs.push((attr, ""));
+            continue;
+        }
+
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        let value = match bytes.get(i) {
+            Some(quote @ (b'"' | b'\'')) => {
+                let value_start = i + 1;
+                let value_end = html[value_start..].find(*quote as char)? + value_start;
+                i = value_end + 1;
+                &html[value_start..value_end]
+            }
+            Some(_) => {
+                let value_start = i;
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>' {
+                    i += 1;
+                }
+                &html[value_start..i]
+            }
+            None => return None,
+        };
+        attrs.push((attr, value));
+    }
+}
+
+fn decode_entities(value: &str) -> String {
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let Some(semi) = rest.find(';').filter(|semi| *semi <= 10) else {
+            decoded.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..semi];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match c {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn clean_text(value: &str) -> Option<String> {
+    let text = decode_entities(value)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if text.is_empty() {
+        return None;
+    }
+
+    Some(text.chars().take(MAX_VALUE_LEN).collect())
+}
+
+fn absolute_url(base_url: &Url, value: &str) -> Option<String> {
+    let url = base_url.join(decode_entities(value).trim()).ok()?;
+    matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+}
+
+/// Title, description, favicon and preview image of a page, read from its `<head>`. The
+/// `<title>` wins over `og:title`, the meta description over `og:description`. Without an
+/// icon link the favicon falls back to `/favicon.ico`.
+pub fn extract_page_metadata(html: &str, base_url: &Url) -> PageMetadata {
+    let mut metadata = PageMetadata::default();
+    let mut og_title = None;
+    let mut og_description = None;
+    let mut base_url = base_url.clone();
+
+    let mut pos = 0;
+    while let Some(offset) = html[pos..].find('<') {
+        let start = pos + offset;
+        let Some(tag) = parse_tag(html, start) else {
+            break;
+        };
+        pos = tag.end;
+
+        match tag.name.as_str() {
+            "/head" | "body" => break,
+            "base" => {
+                if let Some(href) = tag.attr("href").and_then(|href| base_url.join(href).ok()) {
+                    base_url = href;
+                }
+            }
+            "title" if metadata.title.is_none() => {
+                let Some(end) = html[pos..].to_ascii_lowercase().find("</title") else {
+                    break;
+                };
+                metadata.title = clean_text(&html[pos..pos + end]);
+                pos += end;
+            }
+            "meta" => {
+                let key = tag
+                    .attr("property")
+                    .or_else(|| tag.attr("name"))
+                    .map(str::to_ascii_lowercase);
+                let Some(content) = tag.attr("content") else {
+                    continue;
+                };
+                match key.as_deref() {
+                    Some("description") => metadata.description = clean_text(content),
+                    Some("og:title") => og_title = clean_text(content),
+                    Some("og:description") => og_description = clean_text(content),
+                    Some("og:image" | "og:image:url" | "twitter:image")
+                        if metadata.image.is_none() =>
+                    {
+                        metadata.image = absolute_url(&base_url, content);
+                    }
+                    _ => {}
+                }
+            }
+            "link" => {
+                let rel = tag.attr("rel").unwrap_or_default().to_ascii_lowercase();
+                let is_icon = rel.split_whitespace().any(|r| r == "icon");
+                if is_icon
+                    && metadata.favicon.is_none()
+                    && let Some(href) = tag.attr("href")
+                {
+                    metadata.favicon = absolute_url(&base_url, href);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    metadata.title = metadata.title.or(og_title);
+    metadata.description = metadata.description.or(og_description);
+    metadata.favicon = metadata
+        .favicon
+        .or_else(|| absolute_url(&base_url, "/favicon.ico"));
+
+    metadata
+}
@@ -0,0 +1,72 @@
+use std::sync::{Mutex, RwLock};
+
+use fastbloom::AtomicBloomFilter;
+
+/// Room for ids added between rebuilds, so the false positive rate stays close to the target.
+const GROWTH_FACTOR: usize = 2;
+const MIN_EXPECTED_ITEMS: usize = 1024;
+
+/// Bloom filter of known ids, answers "definitely unknown" without a lookup. Until it is
+/// first built, and after `reset`, every id counts as possibly known.
+pub struct IdFilter {
+    false_positive_rate: f64,
+    filter: RwLock<Option<AtomicBloomFilter>>,
+    /// Ids added while a rebuild reads the full set, merged into the new filter.
+    pending: Mutex<Option<Vec<String>>>,
+}
+
+impl IdFilter {
+    pub fn new(false_positive_rate: f64) -> Self {
+        Self {
+            false_positive_rate,
+            filter: RwLock::new(None),
+            pending: Mutex::new(None),
+        }
+    }
+
+    pub fn might_contain(&self, id: &str) -> bool {
+        match self.filter.read().unwrap().as_ref() {
+            Some(filter) => filter.contains(id),
+            None => true,
+        }
+    }
+
+    pub fn insert(&self, id: &str) {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(pending) = pending.as_mut() {
+            pending.push(id.to_string());
+        }
+        if let Some(filter) = self.filter.read().unwrap().as_ref() {
+            filter.insert(id);
+        }
+    }
+
+    /// Must be called before the full id set is read, ids inserted from here on are carried
+    /// over by `finish_rebuild`.
+    pub fn start_rebuild(&self) {
+        *self.pending.lock().unwrap() = Some(Vec::new());
+    }
+
+    pub fn cancel_rebuild(&self) {
+        *self.pending.lock().unwrap() = None;
+    }
+
+    pub fn finish_rebuild(&self, ids: impl ExactSizeIterator<Item = String>) {
+        let filter = AtomicBloomFilter::with_false_pos(self.false_positive_rate)
+            .expected_items((ids.len() * GROWTH_FACTOR).max(MIN_EXPECTED_ITEMS));
+        for id in ids {
+            filter.insert(id.as_str());
+        }
+
+        let mut pending = self.pending.lock().unwrap();
+        for id in pending.take().unwrap_or_default() {
+            filter.insert(id.as_str());
+        }
+        *self.filter.write().unwrap() = Some(filter);
+    }
+
+    /// Stops answering until the next rebuild, for when inserts may have been missed.
+    pub fn reset(&self) {
+        *self.filter.write().unwrap() = None;
+    }
+}
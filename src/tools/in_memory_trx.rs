@@ -0,0 +1,16 @@
+use solar::trx_factory::{TrxContext, TrxFactory, TrxFactoryError};
+
+/// Runs the closure outside of any transaction, for the in-memory repos. Nothing is rolled
+/// back on error, so it only suits tests that check one outcome at a time.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryTrxFactory;
+
+impl TrxFactory for InMemoryTrxFactory {
+    async fn begin<R, E, F>(&self, f: F) -> Result<R, E>
+    where
+        F: AsyncFnOnce(TrxContext) -> Result<R, E>,
+        E: From<TrxFactoryError>,
+    {
+        f(TrxContext::Empty).await
+    }
+}
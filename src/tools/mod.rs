@@ -1,2 +1,17 @@
+pub mod accept_language;
+pub mod any_trx;
+pub mod cache_stats;
+pub mod circuit_breaker;
+pub mod client_ip;
+pub mod failure_log;
+pub mod geoip;
+pub mod html_meta;
+pub mod id_filter;
+#[cfg(test)]
+pub mod in_memory_trx;
 pub mod jwt;
 pub mod password_hash;
+pub mod redis_connection;
+pub mod sqlite_trx;
+pub mod url_normalize;
+pub mod user_agent;
@@ -0,0 +1,160 @@
+use std::{
+    future::Future,
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use redis::{
+    RedisResult,
+    aio::{ConnectionManager, ConnectionManagerConfig},
+};
+use utoipa::ToSchema;
+
+use super::{
+    circuit_breaker::{BreakerState, CircuitBreaker},
+    failure_log::FailureLog,
+};
+use crate::config::RedisConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RedisStatus {
+    Up,
+    /// Commands kept failing, the cache is bypassed until a trial command succeeds.
+    Failing,
+    /// Never connected, retried in the background.
+    Disconnected,
+    /// Not configured, links are only cached in process.
+    Disabled,
+}
+
+/// Redis as an optional accelerator, left out of the config it is not used at all. Commands
+/// are skipped while it is unreachable or the circuit breaker is open, callers then go
+/// straight to the database.
+pub struct RedisConnection {
+    client: redis::Client,
+    connection_config: ConnectionManagerConfig,
+    connection: RwLock<Option<ConnectionManager>>,
+    breaker: CircuitBreaker,
+    bypassed: AtomicU64,
+    connect_failures: FailureLog,
+}
+
+impl RedisConnection {
+    /// Never fails, without a connection every command is skipped until `reconnect`
+    /// succeeds.
+    pub async fn connect(client: redis::Client, redis_config: &RedisConfig) -> Self {
+        let connection_config = ConnectionManagerConfig::new()
+            .set_number_of_retries(1)
+            .set_connection_timeout(Duration::from_millis(redis_config.connect_timeout_ms))
+            .set_response_timeout(Duration::from_millis(redis_config.response_timeout_ms));
+
+        let redis_connection = Self {
+            client,
+            connection_config,
+            connection: RwLock::new(None),
+            breaker: CircuitBreaker::new(
+                redis_config.failure_threshold,
+                Duration::from_secs(redis_config.open_sec),
+            ),
+            bypassed: AtomicU64::new(0),
+            connect_failures: FailureLog::new("connect to redis, running without cache"),
+        };
+        redis_connection.reconnect().await;
+
+        redis_connection
+    }
+
+    /// Once established, the connection manager reconnects on its own.
+    pub async fn reconnect(&self) -> bool {
+        if self.is_connected() {
+            return true;
+        }
+
+        match ConnectionManager::new_with_config(
+            self.client.clone(),
+            self.connection_config.clone(),
+        )
+        .await
+        {
+            Ok(connection) => {
+                *self.connection.write().expect("redis lock poisoned") = Some(connection);
+                self.connect_failures.succeeded();
+                println!("connected to redis");
+                true
+            }
+            Err(e) => {
+                self.connect_failures.failed(e);
+                false
+            }
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection
+            .read()
+            .expect("redis lock poisoned")
+            .is_some()
+    }
+
+    /// Runs the command unless Redis is bypassed. `None` when it was skipped or failed, both
+    /// are counted in `bypassed`. Failures are only logged when they open the breaker.
+    pub async fn run<T, F, Fut>(&self, command: F) -> Option<T>
+    where
+        F: FnOnce(ConnectionManager) -> Fut,
+        Fut: Future<Output = RedisResult<T>>,
+    {
+        let connection = self.connection.read().expect("redis lock poisoned").clone();
+        let Some(connection) = connection.filter(|_| self.breaker.allow()) else {
+            self.bypassed.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+
+        match command(connection).await {
+            Ok(value) => {
+                if self.breaker.record_success() {
+                    println!("redis commands succeed again, using the cache");
+                }
+                Some(value)
+            }
+            Err(e) => {
+                self.bypassed.fetch_add(1, Ordering::Relaxed);
+                if self.breaker.record_failure() {
+                    println!("redis commands keep failing, bypassing the cache: {e}");
+                }
+                None
+            }
+        }
+    }
+
+    pub fn status(&self) -> RedisStatus {
+        if !self.is_connected() {
+            return RedisStatus::Disconnected;
+        }
+
+        match self.breaker.state() {
+            BreakerState::Closed => RedisStatus::Up,
+            BreakerState::Open | BreakerState::HalfOpen => RedisStatus::Failing,
+        }
+    }
+
+    /// Commands skipped or failed since startup.
+    pub fn bypassed(&self) -> u64 {
+        self.bypassed.load(Ordering::Relaxed)
+    }
+}
+
+pub fn spawn_reconnector(redis_connection: Arc<RedisConnection>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if !redis_connection.is_connected() {
+                redis_connection.reconnect().await;
+            }
+        }
+    });
+}
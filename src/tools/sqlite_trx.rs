@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use solar::trx_factory::{TrxContext, TrxFactory, TrxFactoryError};
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use tokio::sync::Mutex;
+
+pub type SharedSqliteTrx = Arc<Mutex<Option<Transaction<'static, Sqlite>>>>;
+
+tokio::task_local! {
+    static CURRENT_TRX: SharedSqliteTrx;
+}
+
+/// `TrxContext` can only carry Postgres transactions, so the open SQLite transaction is
+/// kept in a task local instead. Repos called inside `begin` join it whatever context they
+/// are given, work spawned onto other tasks does not.
+///
+/// Only `begin` takes the database write lock, so keep reads out of it where possible. Reads
+/// outside run in deferred transactions, which WAL mode lets proceed next to a writer.
+#[derive(Clone)]
+pub struct SqliteTrxFactory {
+    pool: SqlitePool,
+}
+
+impl SqliteTrxFactory {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    /// The transaction of the enclosing `begin`, or a new one that is never committed, for
+    /// reads.
+    pub async fn extract_or_create_trx(
+        &self,
+        ctx: TrxContext,
+    ) -> Result<(SharedSqliteTrx, bool), TrxFactoryError> {
+        let _ = ctx;
+        if let Ok(trx) = CURRENT_TRX.try_with(Arc::clone) {
+            return Ok((trx, false));
+        }
+
+        let trx = self.pool.begin().await?;
+        Ok((Arc::new(Mutex::new(Some(trx))), true))
+    }
+}
+
+impl TrxFactory for SqliteTrxFactory {
+    async fn begin<R, E, F>(&self, f: F) -> Result<R, E>
+    where
+        F: AsyncFnOnce(TrxContext) -> Result<R, E>,
+        E: From<TrxFactoryError>,
+    {
+        if CURRENT_TRX.try_with(|_| ()).is_ok() {
+            return f(TrxContext::Empty).await;
+        }
+
+        // takes the write lock up front, a deferred transaction that later writes fails with
+        // SQLITE_BUSY when another one committed in between
+        let trx = self
+            .pool
+            .begin_with("BEGIN IMMEDIATE")
+            .await
+            .map_err(TrxFactoryError::from)?;
+        let shared = Arc::new(Mutex::new(Some(trx)));
+
+        let result = CURRENT_TRX
+            .scope(shared.clone(), f(TrxContext::Empty))
+            .await?;
+        if let Some(trx) = shared.lock().await.take() {
+            trx.commit().await.map_err(TrxFactoryError::from)?;
+        }
+
+        Ok(result)
+    }
+}
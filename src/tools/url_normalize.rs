@@ -0,0 +1,90 @@
+use sha2::{Digest, Sha256};
+use url::Url;
+
+/// A pattern ending with `*` matches by prefix, e.g. `utm_*`.
+fn is_ignored_param(param: &str, ignored_params: &[String]) -> bool {
+    let param = param.to_lowercase();
+
+    ignored_params.iter().any(|pattern| {
+        let pattern = pattern.to_lowercase();
+        match pattern.strip_suffix('*') {
+            Some(prefix) => param.starts_with(prefix),
+            None => param == pattern,
+        }
+    })
+}
+
+/// Lowercases scheme and host, drops default port, fragment, trailing slash and ignored
+/// query params, and sorts the remaining query params.
+pub fn normalize_url(raw: &str, ignored_params: &[String]) -> Option<String> {
+    let mut url = Url::parse(raw.trim()).ok()?;
+    url.set_fragment(None);
+
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !is_ignored_param(key, ignored_params))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    pairs.sort();
+
+    if pairs.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+
+    let path = url.path().to_string();
+    if path.len() > 1 && path.ends_with('/') {
+        url.set_path(path.trim_end_matches('/'));
+    }
+
+    Some(url.to_string())
+}
+
+pub fn normalized_url_hash(raw: &str, ignored_params: &[String]) -> Option<String> {
+    let normalized = normalize_url(raw, ignored_params)?;
+
+    Some(hex::encode(Sha256::digest(normalized.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracking_params() -> Vec<String> {
+        vec!["utm_*".to_string(), "fbclid".to_string()]
+    }
+
+    #[test]
+    fn strips_tracking_params_and_sorts_the_rest() {
+        let normalized = normalize_url(
+            "https://example.com/docs?utm_source=mail&b=2&UTM_Medium=x&fbclid=abc&a=1",
+            &tracking_params(),
+        );
+
+        assert_eq!(
+            normalized.as_deref(),
+            Some("https://example.com/docs?a=1&b=2")
+        );
+    }
+
+    #[test]
+    fn drops_the_query_when_only_tracking_params_are_left() {
+        let normalized = normalize_url(
+            "HTTPS://Example.com:443/docs/?utm_campaign=launch#intro",
+            &tracking_params(),
+        );
+
+        assert_eq!(normalized.as_deref(), Some("https://example.com/docs"));
+    }
+
+    #[test]
+    fn keeps_params_that_only_share_a_prefix_with_exact_patterns() {
+        let normalized = normalize_url("https://example.com/?fbclid_x=1", &tracking_params());
+
+        assert_eq!(
+            normalized.as_deref(),
+            Some("https://example.com/?fbclid_x=1")
+        );
+    }
+}
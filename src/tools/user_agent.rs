@@ -0,0 +1,138 @@
+use utoipa::ToSchema;
+
+const BOT_MARKERS: [&str; 6] = [
+    "bot",
+    "crawler",
+    "spider",
+    "slurp",
+    "facebookexternalhit",
+    "headless",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceClass {
+    Desktop,
+    Mobile,
+    Tablet,
+    Bot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Os {
+    Ios,
+    Android,
+    Windows,
+    MacOs,
+    ChromeOs,
+    Linux,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Browser {
+    Chrome,
+    Safari,
+    Firefox,
+    Edge,
+    Opera,
+    SamsungInternet,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParsedUserAgent {
+    pub device: DeviceClass,
+    pub os: Os,
+    pub browser: Browser,
+}
+
+/// Coarse User-Agent classification, good enough for routing and not meant as a full parser.
+pub fn parse_user_agent(user_agent: &str) -> ParsedUserAgent {
+    let ua = user_agent.to_lowercase();
+
+    let os = if ua.contains("iphone") || ua.contains("ipad") || ua.contains("ipod") {
+        Os::Ios
+    } else if ua.contains("android") {
+        Os::Android
+    } else if ua.contains("windows") {
+        Os::Windows
+    } else if ua.contains("cros ") {
+        // the token, as in "X11; CrOS x86_64", a bare "cros" also matches "microsoft"
+        Os::ChromeOs
+    } else if ua.contains("macintosh") || ua.contains("mac os x") {
+        Os::MacOs
+    } else if ua.contains("linux") {
+        Os::Linux
+    } else {
+        Os::Other
+    };
+
+    let device = if BOT_MARKERS.iter().any(|marker| ua.contains(marker)) {
+        DeviceClass::Bot
+    } else if ua.contains("ipad")
+        || ua.contains("tablet")
+        || (os == Os::Android && !ua.contains("mobile"))
+    {
+        DeviceClass::Tablet
+    } else if ua.contains("mobi") || ua.contains("iphone") || ua.contains("ipod") {
+        DeviceClass::Mobile
+    } else {
+        DeviceClass::Desktop
+    };
+
+    // order matters, most Chromium based browsers also claim to be Chrome and Safari
+    let browser = if ua.contains("edg/") || ua.contains("edge/") || ua.contains("edgios") {
+        Browser::Edge
+    } else if ua.contains("opr/") || ua.contains("opera") {
+        Browser::Opera
+    } else if ua.contains("samsungbrowser") {
+        Browser::SamsungInternet
+    } else if ua.contains("firefox/") || ua.contains("fxios") {
+        Browser::Firefox
+    } else if ua.contains("chrome/") || ua.contains("crios") || ua.contains("chromium") {
+        Browser::Chrome
+    } else if ua.contains("safari/") {
+        Browser::Safari
+    } else {
+        Browser::Other
+    };
+
+    ParsedUserAgent {
+        device,
+        os,
+        browser,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_os_by_token() {
+        let cases = [
+            (
+                "Mozilla/5.0 (X11; CrOS x86_64 14541.0.0) AppleWebKit/537.36 (KHTML, like Gecko) \
+                 Chrome/120.0.0.0 Safari/537.36",
+                Os::ChromeOs,
+            ),
+            (
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64; Microsoft Windows) AppleWebKit/537.36 \
+                 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.0.0",
+                Os::Windows,
+            ),
+            (
+                "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 \
+                 (KHTML, like Gecko) Microsoft Outlook 16.78",
+                Os::MacOs,
+            ),
+        ];
+
+        for (user_agent, os) in cases {
+            assert_eq!(parse_user_agent(user_agent).os, os, "{user_agent}");
+        }
+    }
+}
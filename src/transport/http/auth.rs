@@ -6,7 +6,7 @@ use crate::{
 };
 use axum::{
     extract::{Request, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode, header},
     middleware::Next,
     response::IntoResponse,
 };
@@ -18,20 +18,35 @@ pub struct MiddlewareUserResponse {
     pub email: String,
 }
 
+/// `Authorization: Bearer <jwt>`, for clients that do not keep cookies.
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+/// Authenticates with the `token` cookie set on login, or with the same jwt as a bearer
+/// token.
 pub async fn user_middleware(
     State(state): State<AppState>,
     jar: CookieJar,
     mut req: Request,
     next: Next,
 ) -> impl IntoResponse {
-    let cookie = match jar.get("token") {
-        Some(c) => c,
-        None => return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response(),
+    let jwt = match jar.get("token") {
+        Some(c) => c.value().to_string(),
+        None => match bearer_token(req.headers()) {
+            Some(jwt) => jwt.to_string(),
+            None => return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response(),
+        },
     };
 
     let secret_key = env::var("SECRET_JWT").expect("SECRET_JWT must be set");
 
-    let token = match decode_token(cookie.value(), &secret_key) {
+    let token = match decode_token(&jwt, &secret_key) {
         Ok(t) => t,
         Err(_) => return (StatusCode::UNAUTHORIZED, "Invalid token").into_response(),
     };
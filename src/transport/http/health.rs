@@ -0,0 +1,41 @@
+use axum::{Json, extract::State};
+use utoipa::ToSchema;
+
+use crate::{AppState, tools::redis_connection::RedisStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceStatus {
+    Ok,
+    /// The configured Redis is unavailable, every lookup goes to the database.
+    Degraded,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, ToSchema)]
+pub struct HealthResponse {
+    pub status: ServiceStatus,
+    pub redis: RedisStatus,
+}
+
+/// Service health, degraded while a configured Redis is unavailable
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "short-link",
+    responses(
+        (status = 200, description = "OK", body = HealthResponse),)
+)]
+pub async fn health_get_handler(State(state): State<AppState>) -> Json<HealthResponse> {
+    let redis = state
+        .redis_connection
+        .as_ref()
+        .map_or(RedisStatus::Disabled, |redis_connection| {
+            redis_connection.status()
+        });
+    let status = match redis {
+        RedisStatus::Up | RedisStatus::Disabled => ServiceStatus::Ok,
+        RedisStatus::Failing | RedisStatus::Disconnected => ServiceStatus::Degraded,
+    };
+
+    Json(HealthResponse { status, redis })
+}